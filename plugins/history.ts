@@ -1,21 +1,47 @@
-import { invoke } from "@tauri-apps/api/core";
-import type { HistoryItem } from "~/types/types";
+import { convertFileSrc, invoke } from "@tauri-apps/api/core";
+import type {
+  ColorAnalysis,
+  ColorFormat,
+  ContentKind,
+  EncryptionStatus,
+  HistoryItem,
+  ImageSweepReport,
+  ImportReport,
+  ItemInfo,
+  LanguageCount,
+  RichFormat,
+  SearchResult,
+  Selection,
+  Source,
+} from "~/types/types";
 
 export default defineNuxtPlugin(() => {
   return {
     provide: {
       history: {
-        async getHistory(): Promise<HistoryItem[]> {
-          return await invoke<HistoryItem[]>("get_history");
+        async getHistory(pinnedFirst = false): Promise<HistoryItem[]> {
+          return await invoke<HistoryItem[]>("get_history", { pinnedFirst });
         },
 
         async addHistoryItem(item: HistoryItem): Promise<void> {
           await invoke<void>("add_history_item", { item });
         },
 
-        async searchHistory(query: string): Promise<HistoryItem[]> {
+        async searchHistory(
+          query: string,
+          language?: string,
+          kind?: ContentKind,
+          windowTitle?: string,
+          url?: string
+        ): Promise<SearchResult[]> {
           try {
-            return await invoke<HistoryItem[]>("search_history", { query });
+            return await invoke<SearchResult[]>("search_history", {
+              query,
+              language,
+              kind,
+              windowTitle,
+              url,
+            });
           } catch (error) {
             console.error("Error searching history:", error);
             return [];
@@ -24,12 +50,14 @@ export default defineNuxtPlugin(() => {
 
         async loadHistoryChunk(
           offset: number,
-          limit: number
+          limit: number,
+          pinnedFirst = false
         ): Promise<HistoryItem[]> {
           try {
             return await invoke<HistoryItem[]>("load_history_chunk", {
               offset,
               limit,
+              pinnedFirst,
             });
           } catch (error) {
             console.error("Error loading history chunk:", error);
@@ -37,6 +65,18 @@ export default defineNuxtPlugin(() => {
           }
         },
 
+        async revealHistoryItem(id: string): Promise<string> {
+          return await invoke<string>("reveal_history_item", { id });
+        },
+
+        async getSources(): Promise<Source[]> {
+          return await invoke<Source[]>("get_sources");
+        },
+
+        async getItemInfo(id: string): Promise<ItemInfo> {
+          return await invoke<ItemInfo>("get_item_info", { id });
+        },
+
         async deleteHistoryItem(id: string): Promise<void> {
           await invoke<void>("delete_history_item", { id });
         },
@@ -45,15 +85,105 @@ export default defineNuxtPlugin(() => {
           await invoke<void>("clear_history");
         },
 
+        async pinHistoryItem(id: string): Promise<void> {
+          await invoke<void>("pin_history_item", { id });
+        },
+
+        async unpinHistoryItem(id: string): Promise<void> {
+          await invoke<void>("unpin_history_item", { id });
+        },
+
+        async reorderPinnedItems(ids: string[]): Promise<void> {
+          await invoke<void>("reorder_pinned_items", { ids });
+        },
+
+        async formatJson(content: string): Promise<string> {
+          return await invoke<string>("format_json", { content });
+        },
+
+        async convertColor(id: string, format: ColorFormat): Promise<string> {
+          return await invoke<string>("convert_color", { id, format });
+        },
+
+        async analyzeColor(
+          color: string,
+          background?: string
+        ): Promise<ColorAnalysis> {
+          return await invoke<ColorAnalysis>("analyze_color", {
+            color,
+            background,
+          });
+        },
+
+        async getHistoryLanguages(): Promise<LanguageCount[]> {
+          return await invoke<LanguageCount[]>("get_history_languages");
+        },
+
+        async sweepOrphanedImages(dryRun = false): Promise<ImageSweepReport> {
+          return await invoke<ImageSweepReport>("sweep_orphaned_images", {
+            dryRun,
+          });
+        },
+
         async writeAndPaste(data: {
           content: string;
           contentType: string;
+          id?: string;
+          plain?: boolean;
+          colorFormat?: ColorFormat;
+          selection?: Selection;
         }): Promise<void> {
           await invoke<void>("write_and_paste", data);
         },
 
-        async readImage(data: { filename: string }): Promise<string> {
-          return await invoke<string>("read_image", data);
+        async readImage(id: string): Promise<string> {
+          return await invoke<string>("read_image", { id });
+        },
+
+        imageUrl(id: string, variant: "thumb" | "full" = "full"): string {
+          return `${convertFileSrc(id, "qopy-image")}?variant=${variant}`;
+        },
+
+        async getRichContent(
+          id: string,
+          format: RichFormat
+        ): Promise<string | null> {
+          return await invoke<string | null>("get_rich_content", { id, format });
+        },
+
+        async exportHistory(data: {
+          path: string;
+          ids?: string[];
+          includeSettings?: boolean;
+        }): Promise<number> {
+          return await invoke<number>("export_history", data);
+        },
+
+        async importHistory(data: {
+          path: string;
+          importSettings?: boolean;
+        }): Promise<ImportReport> {
+          return await invoke<ImportReport>("import_history", data);
+        },
+
+        async getEncryptionStatus(): Promise<EncryptionStatus> {
+          return await invoke<EncryptionStatus>("get_encryption_status");
+        },
+
+        async unlockHistory(passphrase?: string): Promise<void> {
+          await invoke<void>("unlock_history", { passphrase });
+        },
+
+        async enableEncryption(passphrase?: string): Promise<void> {
+          await invoke<void>("enable_encryption", { passphrase });
+        },
+
+        async rotateEncryptionKey(passphrase?: string): Promise<void> {
+          await invoke<void>("rotate_encryption_key", { passphrase });
+        },
+
+        async disableEncryption(): Promise<void> {
+          await invoke<void>("disable_encryption");
         },
       },
     },
@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import type { AppFilter, CaptureStatus } from "~/types/types";
 
 export default defineNuxtPlugin(() => {
   return {
@@ -11,6 +12,38 @@ export default defineNuxtPlugin(() => {
         async saveSetting(key: string, value: string): Promise<void> {
           await invoke<void>("save_setting", { key, value });
         },
+
+        async getAppFilter(): Promise<AppFilter> {
+          return await invoke<AppFilter>("get_app_filter");
+        },
+
+        async saveAppFilter(filter: AppFilter): Promise<void> {
+          await invoke<void>("save_app_filter", { filter });
+        },
+
+        async excludeApp(appName: string): Promise<void> {
+          await invoke<void>("exclude_app", { appName });
+        },
+
+        async unexcludeApp(appName: string): Promise<void> {
+          await invoke<void>("unexclude_app", { appName });
+        },
+
+        async getCaptureStatus(): Promise<CaptureStatus> {
+          return await invoke<CaptureStatus>("get_capture_status");
+        },
+
+        async pauseCapture(minutes?: number): Promise<CaptureStatus> {
+          return await invoke<CaptureStatus>("pause_capture", { minutes });
+        },
+
+        async resumeCapture(): Promise<CaptureStatus> {
+          return await invoke<CaptureStatus>("resume_capture");
+        },
+
+        async toggleCapture(): Promise<CaptureStatus> {
+          return await invoke<CaptureStatus>("toggle_capture");
+        },
       },
     },
   };
@@ -0,0 +1,161 @@
+use crate::db::settings::get_capture_pause_settings;
+use crate::utils::types::CaptureStatus;
+use chrono::{ DateTime, Utc };
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use sqlx::{ Row, SqlitePool };
+use std::sync::atomic::{ AtomicBool, AtomicU64, Ordering };
+use tauri::{ AppHandle, Emitter, Manager };
+use tauri_plugin_aptabase::EventTracker;
+
+/// Settings key the pause is persisted under when `persist` is enabled.
+const STATE_KEY: &str = "capture_pause_state";
+
+lazy_static! {
+    static ref CAPTURE_PAUSED: AtomicBool = AtomicBool::new(false);
+    static ref PAUSED_UNTIL: RwLock<Option<DateTime<Utc>>> = RwLock::new(None);
+    /// Bumped on every change so a stale resume timer doesn't end a newer pause
+    static ref PAUSE_GENERATION: AtomicU64 = AtomicU64::new(0);
+}
+
+/// Whether the clipboard monitor should ignore updates.
+pub fn is_paused() -> bool {
+    CAPTURE_PAUSED.load(Ordering::SeqCst)
+}
+
+pub fn status() -> CaptureStatus {
+    CaptureStatus {
+        paused: is_paused(),
+        until: *PAUSED_UNTIL.read(),
+    }
+}
+
+/// Restores a persisted pause, unless it has run out while the app was closed.
+pub fn setup(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app_handle.state::<SqlitePool>();
+        if !get_capture_pause_settings(&pool).await.persist {
+            return;
+        }
+
+        let row = sqlx
+            ::query("SELECT value FROM settings WHERE key = ?")
+            .bind(STATE_KEY)
+            .fetch_optional(&*pool).await
+            .ok()
+            .flatten();
+        let stored = row.and_then(|r|
+            serde_json::from_str::<CaptureStatus>(&r.get::<String, _>("value")).ok()
+        );
+
+        if let Some(stored) = stored {
+            if stored.paused && !matches!(stored.until, Some(until) if until <= Utc::now()) {
+                set_paused(&app_handle, true, stored.until).await;
+            }
+        }
+    });
+}
+
+/// Pauses capture for `minutes`, or indefinitely when that is `None`.
+pub async fn pause(app_handle: &AppHandle, minutes: Option<u64>) -> CaptureStatus {
+    let until = minutes
+        .filter(|minutes| *minutes > 0)
+        .map(|minutes| Utc::now() + chrono::Duration::minutes(minutes as i64));
+    set_paused(app_handle, true, until).await
+}
+
+pub async fn resume(app_handle: &AppHandle) -> CaptureStatus {
+    set_paused(app_handle, false, None).await
+}
+
+/// Used by the tray item and hotkey, pausing for the configured default duration.
+pub async fn toggle(app_handle: &AppHandle) -> CaptureStatus {
+    if is_paused() {
+        resume(app_handle).await
+    } else {
+        let settings = get_capture_pause_settings(&app_handle.state::<SqlitePool>()).await;
+        pause(app_handle, settings.default_minutes).await
+    }
+}
+
+async fn set_paused(app_handle: &AppHandle, paused: bool, until: Option<DateTime<Utc>>) -> CaptureStatus {
+    let generation = apply(paused, until);
+
+    if let Some(until) = until {
+        let handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            let remaining = (until - Utc::now()).to_std().unwrap_or_default();
+            tokio::time::sleep(remaining).await;
+            if PAUSE_GENERATION.load(Ordering::SeqCst) == generation {
+                apply(false, None);
+                notify(&handle, status()).await;
+            }
+        });
+    }
+
+    notify(app_handle, status()).await
+}
+
+fn apply(paused: bool, until: Option<DateTime<Utc>>) -> u64 {
+    let generation = PAUSE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    *PAUSED_UNTIL.write() = until;
+    CAPTURE_PAUSED.store(paused, Ordering::SeqCst);
+    generation
+}
+
+async fn notify(app_handle: &AppHandle, status: CaptureStatus) -> CaptureStatus {
+    if let Err(e) = persist(app_handle, &status).await {
+        eprintln!("Failed to persist capture pause: {}", e);
+    }
+
+    let _ = app_handle.emit("capture-paused-changed", &status);
+    let _ = app_handle.track_event(
+        "capture_paused",
+        Some(serde_json::json!({
+        "paused": status.paused,
+        "timed": status.until.is_some()
+    }))
+    );
+
+    status
+}
+
+async fn persist(app_handle: &AppHandle, status: &CaptureStatus) -> Result<(), String> {
+    let pool = app_handle.state::<SqlitePool>();
+    if status.paused && get_capture_pause_settings(&pool).await.persist {
+        let json = serde_json::to_string(status).map_err(|e| e.to_string())?;
+        sqlx
+            ::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
+            .bind(STATE_KEY)
+            .bind(json)
+            .execute(&*pool).await
+            .map_err(|e| e.to_string())?;
+    } else {
+        sqlx
+            ::query("DELETE FROM settings WHERE key = ?")
+            .bind(STATE_KEY)
+            .execute(&*pool).await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_capture_status() -> CaptureStatus {
+    status()
+}
+
+#[tauri::command]
+pub async fn pause_capture(app_handle: AppHandle, minutes: Option<u64>) -> Result<CaptureStatus, String> {
+    Ok(pause(&app_handle, minutes).await)
+}
+
+#[tauri::command]
+pub async fn resume_capture(app_handle: AppHandle) -> Result<CaptureStatus, String> {
+    Ok(resume(&app_handle).await)
+}
+
+#[tauri::command]
+pub async fn toggle_capture(app_handle: AppHandle) -> Result<CaptureStatus, String> {
+    Ok(toggle(&app_handle).await)
+}
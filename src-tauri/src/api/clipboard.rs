@@ -1,12 +1,13 @@
 use tauri_plugin_aptabase::EventTracker;
 use base64::{ engine::general_purpose::STANDARD, Engine };
-// use hyperpolyglot;
+use chrono::Utc;
+use clipboard_rs::{ Clipboard as _, ClipboardContent, ClipboardContext };
 use lazy_static::lazy_static;
 use rdev::{ simulate, EventType, Key };
 use regex::Regex;
 use sqlx::SqlitePool;
 use std::fs;
-use std::sync::atomic::{ AtomicBool, Ordering };
+use std::sync::atomic::{ AtomicBool, AtomicU64, Ordering };
 use std::{ thread, time::Duration };
 use tauri::{ AppHandle, Emitter, Listener, Manager };
 use tauri_plugin_clipboard::Clipboard;
@@ -14,25 +15,103 @@ use tokio::runtime::Runtime as TokioRuntime;
 use url::Url;
 use uuid::Uuid;
 
+use crate::api::{ self, capture };
 use crate::db;
-use crate::utils::commands::get_app_info;
+use crate::db::settings::{ CaptureDebounce, CaptureLimits, SensitiveAction };
+use crate::utils::color::{ is_color_literal, Color, ColorFormat };
+use crate::utils::apps::get_app_info;
+use crate::utils::debounce::Coalescer;
+use crate::utils::detectors::detect_kind;
 use crate::utils::favicon::fetch_favicon_as_base64;
-use crate::utils::types::{ ContentType, HistoryItem };
+use crate::utils::hash::hash_image;
+use crate::utils::language::detect_language;
+use crate::utils::limits::{ check_image, limit_files, limit_text, ImageFit, Limited };
+use crate::utils::secrets::{ self, SecretVerdict };
+use crate::utils::thumbnail;
+use crate::utils::types::{ CaptureLimited, ContentType, HistoryItem, RichFormat, Selection };
+use crate::utils::window::{ self, HTML_FORMAT, SOURCE_URL_FORMATS };
 
 lazy_static! {
     static ref IS_PROGRAMMATIC_PASTE: AtomicBool = AtomicBool::new(false);
+    /// Debounce window for clipboard updates, see `CaptureDebounce`
+    static ref DEBOUNCE_MS: AtomicU64 = AtomicU64::new(CaptureDebounce::default().window_ms);
 }
 
+/// Some apps fire several updates per copy, only the last of a burst is captured.
+static UPDATES: Coalescer = Coalescer::new();
+
 #[tauri::command]
 pub async fn write_and_paste(
     app_handle: AppHandle,
     content: String,
-    content_type: String
+    content_type: String,
+    id: Option<String>,
+    plain: Option<bool>,
+    color_format: Option<ColorFormat>,
+    selection: Option<Selection>
 ) -> Result<(), String> {
     let clipboard = app_handle.state::<Clipboard>();
 
+    // The stored content wins over what the frontend sent, which is masked for sensitive items
+    let content = match &id {
+        Some(id) if content_type != "image" => {
+            let pool = app_handle.state::<SqlitePool>();
+            db::history::get_content(&pool, id).await?.unwrap_or(content)
+        }
+        _ => content,
+    };
+
+    // Color items can be pasted in another notation than they were copied in
+    let content = match color_format {
+        Some(format) if content_type == "color" =>
+            Color::parse(&content)
+                .and_then(|color| color.format(format))
+                .unwrap_or(content),
+        _ => content,
+    };
+
+    // PRIMARY is pasted with a middle click wherever the pointer is, so that
+    // is left to the user rather than simulated
+    if selection == Some(Selection::Primary) {
+        if content_type == "image" || content_type == "file" {
+            return Err("Only text can be put into the primary selection".to_string());
+        }
+        api::selection::write_primary(content)?;
+
+        let _ = app_handle.track_event(
+            "clipboard_paste",
+            Some(serde_json::json!({
+            "content_type": content_type,
+            "selection": "primary"
+        }))
+        );
+        return Ok(());
+    }
+
+    // Restore the rich form of an item unless the plain form was asked for
+    let rich = match (&id, plain.unwrap_or(false)) {
+        (Some(id), false) if content_type == "text" || content_type == "code" => {
+            let pool = app_handle.state::<SqlitePool>();
+            match db::history::get_representation(&pool, id, RichFormat::Html).await? {
+                Some(html) => Some((RichFormat::Html, html)),
+                None =>
+                    db::history
+                        ::get_representation(&pool, id, RichFormat::Rtf).await?
+                        .map(|rtf| (RichFormat::Rtf, rtf)),
+            }
+        }
+        _ => None,
+    };
+
     match content_type.as_str() {
-        "text" => clipboard.write_text(content).map_err(|e| e.to_string())?,
+        "text" | "code" =>
+            match rich {
+                Some((RichFormat::Html, html)) => {
+                    clipboard.write_html_and_text(html, content).map_err(|e| e.to_string())?
+                }
+                Some((RichFormat::Rtf, rtf)) => write_rtf_and_text(rtf, content)?,
+                None => clipboard.write_text(content).map_err(|e| e.to_string())?,
+            }
         "link" => clipboard.write_text(content).map_err(|e| e.to_string())?,
         "color" => clipboard.write_text(content).map_err(|e| e.to_string())?,
         "image" => {
@@ -97,128 +176,160 @@ pub fn setup(app: &AppHandle) {
     let app_handle = app.clone();
     let runtime = TokioRuntime::new().expect("Failed to create Tokio runtime");
 
+    let handle = app_handle.clone();
+    runtime.spawn(async move {
+        let debounce = db::settings::get_capture_debounce(&handle.state::<SqlitePool>()).await;
+        DEBOUNCE_MS.store(debounce.window_ms, Ordering::SeqCst);
+    });
+
+    app_handle.listen("update-capture-debounce", |event| {
+        let debounce = serde_json
+            ::from_str::<String>(event.payload())
+            .ok()
+            .and_then(|value| serde_json::from_str::<CaptureDebounce>(&value).ok())
+            .unwrap_or_default();
+        DEBOUNCE_MS.store(debounce.window_ms, Ordering::SeqCst);
+    });
+
     app_handle.clone().listen("plugin:clipboard://clipboard-monitor/update", move |_event| {
         let app_handle = app_handle.clone();
-        runtime.block_on(async move {
+        // Spawned rather than blocked on, so later updates of a burst reach the coalescer
+        runtime.spawn(async move {
+            // Checked before coalescing so our own writes don't hold back a copy
             if IS_PROGRAMMATIC_PASTE.load(Ordering::SeqCst) {
                 return;
             }
+            let window = Duration::from_millis(DEBOUNCE_MS.load(Ordering::SeqCst));
+            if !UPDATES.settle(window).await {
+                return;
+            }
+            // Also keeps the capture of one copy from overlapping the next
+            let _write = db::WRITE_LOCK.lock().await;
+
+            if capture::is_paused() {
+                return;
+            }
 
             let clipboard = app_handle.state::<Clipboard>();
             let available_types = clipboard.available_types().unwrap();
 
-            let (app_name, app_icon) = get_app_info();
+            let (app_name, app_path, app_icon) = get_app_info();
+
+            let filter = db::settings::get_app_filter_setting(&app_handle.state::<SqlitePool>()).await;
+            if !filter.allows(&app_name) {
+                log::debug!("Skipping clipboard capture from excluded app {}", app_name);
+                return;
+            }
+
+            // Copies password managers mark as concealed are dropped or kept masked for a short time
+            let sensitive_until = if is_concealed_copy() {
+                let policy = db::settings::get_sensitive_policy(&app_handle.state::<SqlitePool>()).await;
+                match policy.action {
+                    SensitiveAction::Drop => {
+                        log::debug!("Skipping concealed clipboard content from {}", app_name);
+                        return;
+                    }
+                    SensitiveAction::Expire => {
+                        Some(Utc::now() + chrono::Duration::seconds(policy.ttl_seconds as i64))
+                    }
+                }
+            } else {
+                None
+            };
+            let window_title = window::active_window_title();
+            let source_url = read_source_url();
+            let prepare = move |mut item: HistoryItem| {
+                item.source_path = app_path.clone();
+                item.window_title = window_title.clone();
+                item.source_url = source_url.clone();
+                if let Some(until) = sensitive_until {
+                    item.sensitive = true;
+                    item.expires_at = Some(until);
+                }
+                item
+            };
 
             match get_pool(&app_handle).await {
                 Ok(pool) => {
                     if available_types.image {
                         println!("Handling image change");
-                        if let Ok(image_data) = clipboard.read_image_base64() {
-                            let file_path = save_image_to_file(&app_handle, &image_data).await
-                                .map_err(|e| e.to_string())
-                                .unwrap_or_else(|e| e);
-                            let _ = db::history::add_history_item(
-                                app_handle.clone(),
-                                pool,
-                                HistoryItem::new(
+                        let image_bytes = clipboard
+                            .read_image_base64()
+                            .ok()
+                            .and_then(|data| STANDARD.decode(data).ok());
+                        let limits = db::settings::get_capture_limits(&pool).await;
+                        if let Some(bytes) = image_bytes.and_then(|bytes| fit_image(&app_handle, bytes, &limits)) {
+                            let content_hash = hash_image(&bytes).ok();
+
+                            // Same pixels as an existing item, bump it instead of writing another file
+                            let bumped = match &content_hash {
+                                Some(hash) => db::history::bump_by_hash(&pool, hash).await.unwrap_or(false),
+                                None => false,
+                            };
+
+                            if !bumped {
+                                let (file_path, dimensions) = save_image_to_file(&app_handle, &bytes).await
+                                    .unwrap_or_else(|e| (e.to_string(), None));
+                                let mut item = prepare(HistoryItem::new(
                                     app_name,
                                     ContentType::Image,
                                     file_path,
                                     None,
                                     app_icon,
                                     None
-                                )
-                            ).await;
+                                ));
+                                item.content_hash = content_hash;
+                                item.width = dimensions.map(|(width, _)| width);
+                                item.height = dimensions.map(|(_, height)| height);
+                                let _ = db::history::add_history_item(app_handle.clone(), pool, item).await;
+                            }
                         }
                     } else if available_types.files {
                         println!("Handling files change");
                         if let Ok(files) = clipboard.read_files() {
+                            let limits = db::settings::get_capture_limits(&pool).await;
+                            let files = match limit_files(files, &limits) {
+                                Limited::Within(files) => files,
+                                Limited::Over { value, notice } => {
+                                    notify_limited(&app_handle, notice);
+                                    value.unwrap_or_default()
+                                }
+                            };
                             for file in files {
                                 let _ = db::history::add_history_item(
                                     app_handle.clone(),
                                     pool.clone(),
-                                    HistoryItem::new(
+                                    prepare(HistoryItem::new(
                                         app_name.clone(),
                                         ContentType::File,
                                         file,
                                         None,
                                         app_icon.clone(),
                                         None
-                                    )
+                                    ))
                                 ).await;
                             }
                         }
                     } else if available_types.text {
                         println!("Handling text change");
                         if let Ok(text) = clipboard.read_text() {
-                            let text = text.to_string();
-                            let url_regex = Regex::new(
-                                r"^https?://(?:www\.)?[-a-zA-Z0-9@:%._\+~#=]{1,256}\.[a-zA-Z0-9()]{1,6}\b(?:[-a-zA-Z0-9()@:%_\+.~#?&//=]*)$"
-                            ).unwrap();
-
-                            if url_regex.is_match(&text) {
-                                if let Ok(url) = Url::parse(&text) {
-                                    let favicon = match fetch_favicon_as_base64(url).await {
-                                        Ok(Some(f)) => Some(f),
-                                        _ => None,
-                                    };
-
-                                    let _ = db::history::add_history_item(
-                                        app_handle.clone(),
-                                        pool,
-                                        HistoryItem::new(
-                                            app_name,
-                                            ContentType::Link,
-                                            text,
-                                            favicon,
-                                            app_icon,
-                                            None
-                                        )
-                                    ).await;
-                                }
-                            } else {
-                                if text.is_empty() {
-                                    return;
-                                }
-
-                                // Temporarily disabled code detection
-                                /*if let Some(detection) = hyperpolyglot::detect_from_text(&text) {
-                                        let language = match detection {
-                                            hyperpolyglot::Detection::Heuristics(lang) => lang.to_string(),
-                                            _ => detection.language().to_string(),
-                                        };
-
-                                        let _ = db::history::add_history_item(
-                                            pool,
-                                            HistoryItem::new(app_name, ContentType::Code, text, None, app_icon, Some(language))
-                                        ).await;
-                                    } else*/ if crate::utils::commands::detect_color(&text) {
-                                    let _ = db::history::add_history_item(
-                                        app_handle.clone(),
-                                        pool,
-                                        HistoryItem::new(
-                                            app_name,
-                                            ContentType::Color,
-                                            text,
-                                            None,
-                                            app_icon,
-                                            None
-                                        )
-                                    ).await;
-                                } else {
-                                    let _ = db::history::add_history_item(
-                                        app_handle.clone(),
-                                        pool,
-                                        HistoryItem::new(
-                                            app_name,
-                                            ContentType::Text,
-                                            text.clone(),
-                                            None,
-                                            app_icon,
-                                            None
-                                        )
-                                    ).await;
+                            let stored = store_text(
+                                &app_handle,
+                                pool,
+                                text.to_string(),
+                                app_name,
+                                app_icon,
+                                prepare,
+                                |keep_rich| {
+                                    read_representations(
+                                        &clipboard,
+                                        keep_rich && available_types.html,
+                                        keep_rich && available_types.rtf
+                                    )
                                 }
+                            ).await;
+                            if !stored {
+                                return;
                             }
                         }
                     } else {
@@ -246,6 +357,267 @@ pub fn setup(app: &AppHandle) {
     });
 }
 
+/// Stores captured text as a link, code, color or plain text item. `read_rich`
+/// returns the rich representations to keep, it's passed `false` once a secret
+/// was redacted from the text. Returns `false` when the text was skipped.
+pub(crate) async fn store_text(
+    app_handle: &AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    mut text: String,
+    app_name: String,
+    app_icon: Option<String>,
+    prepare: impl Fn(HistoryItem) -> HistoryItem,
+    read_rich: impl FnOnce(bool) -> Vec<(RichFormat, String)>
+) -> bool {
+    let mut keep_rich = true;
+
+    // Checked first so a huge copy isn't scanned for secrets in full
+    let limits = db::settings::get_capture_limits(&pool).await;
+    match limit_text(text, &limits) {
+        Limited::Within(within) => {
+            text = within;
+        }
+        Limited::Over { value, notice } => {
+            notify_limited(app_handle, notice);
+            let Some(value) = value else {
+                return false;
+            };
+            // The rich representations hold the whole copy
+            text = value;
+            keep_rich = false;
+        }
+    }
+    // Rich formats are held to the text limit as well
+    let read_rich = |keep_rich: bool| -> Vec<(RichFormat, String)> {
+        read_rich(keep_rich)
+            .into_iter()
+            .filter(|(_, data)| limits.max_text_bytes.map_or(true, |max| (data.len() as u64) <= max))
+            .collect()
+    };
+
+    // Tokens and keys are skipped, redacted or kept masked for a short time
+    let secret_detection = db::settings::get_secret_detection(&pool).await;
+    let secret_until = match secrets::scan(&text, &secret_detection) {
+        SecretVerdict::Clean => None,
+        SecretVerdict::Skip { rules } => {
+            log::debug!("Skipping clipboard content matching {}", rules.join(", "));
+            return false;
+        }
+        SecretVerdict::Redact { text: redacted, .. } => {
+            // The rich representations would still contain the secret
+            text = redacted;
+            keep_rich = false;
+            None
+        }
+        SecretVerdict::Expire { .. } => {
+            Some(Utc::now() + chrono::Duration::seconds(secret_detection.ttl_seconds as i64))
+        }
+    };
+    let prepare = move |item: HistoryItem| {
+        let mut item = prepare(item);
+        if let Some(until) = secret_until {
+            item.sensitive = true;
+            item.expires_at = Some(item.expires_at.map_or(until, |t| t.min(until)));
+        }
+        item
+    };
+
+    let url_regex = Regex::new(
+        r"^https?://(?:www\.)?[-a-zA-Z0-9@:%._\+~#=]{1,256}\.[a-zA-Z0-9()]{1,6}\b(?:[-a-zA-Z0-9()@:%_\+.~#?&//=]*)$"
+    ).unwrap();
+
+    if url_regex.is_match(&text) {
+        if let Ok(url) = Url::parse(&text) {
+            let favicon = match fetch_favicon_as_base64(url).await {
+                Ok(Some(f)) => Some(f),
+                _ => None,
+            };
+
+            let _ = db::history::add_history_item(
+                app_handle.clone(),
+                pool,
+                prepare(HistoryItem::new(
+                    app_name,
+                    ContentType::Link,
+                    text,
+                    favicon,
+                    app_icon,
+                    None
+                ))
+            ).await;
+        }
+    } else {
+        if text.is_empty() {
+            return false;
+        }
+
+        // Kinds apply to code too, JSON or YAML is often classified as code
+        let kind = detect_kind(&text);
+
+        if let Some(detection) = detect_language(&text) {
+            log::debug!(
+                "Detected {} code with confidence {:.2}",
+                detection.language,
+                detection.confidence
+            );
+            let mut item = prepare(HistoryItem::new(
+                app_name,
+                ContentType::Code,
+                text,
+                None,
+                app_icon,
+                Some(detection.language.to_string())
+            ));
+            item.kind = kind;
+            item.representations = read_rich(keep_rich);
+            let _ = db::history::add_history_item(app_handle.clone(), pool, item).await;
+        } else if is_color_literal(&text) {
+            let _ = db::history::add_history_item(
+                app_handle.clone(),
+                pool,
+                prepare(HistoryItem::new(
+                    app_name,
+                    ContentType::Color,
+                    text,
+                    None,
+                    app_icon,
+                    None
+                ))
+            ).await;
+        } else {
+            let mut item = prepare(HistoryItem::new(
+                app_name,
+                ContentType::Text,
+                text.clone(),
+                None,
+                app_icon,
+                None
+            ));
+            item.kind = kind;
+            item.representations = read_rich(keep_rich);
+            let _ = db::history::add_history_item(app_handle.clone(), pool, item).await;
+        }
+    }
+
+    true
+}
+
+/// Applies the image limits, returning the bytes to store or `None` when the
+/// image is skipped.
+fn fit_image(app_handle: &AppHandle, bytes: Vec<u8>, limits: &CaptureLimits) -> Option<Vec<u8>> {
+    let Ok((width, height)) = thumbnail::dimensions(&bytes) else {
+        return Some(bytes);
+    };
+    let Some((fit, notice)) = check_image(width, height, bytes.len() as u64, limits) else {
+        return Some(bytes);
+    };
+    notify_limited(app_handle, notice);
+
+    let fitted = match fit {
+        ImageFit::Skip => {
+            return None;
+        }
+        ImageFit::Downscale(max_pixels) => thumbnail::downscale(&bytes, max_pixels),
+        ImageFit::Thumbnail => thumbnail::make_thumbnail(&bytes).map(|(png, _, _)| png),
+    };
+    match fitted {
+        Ok(png) => Some(png),
+        Err(e) => {
+            eprintln!("Failed to shrink oversized image: {}", e);
+            None
+        }
+    }
+}
+
+/// Sets RTF and its plain text in one write, so apps without RTF support
+/// still get the text.
+fn write_rtf_and_text(rtf: String, text: String) -> Result<(), String> {
+    ClipboardContext::new()
+        .and_then(|ctx| ctx.set(vec![ClipboardContent::Text(text), ClipboardContent::Rtf(rtf)]))
+        .map_err(|e| e.to_string())
+}
+
+/// Tells the frontend why a copy was skipped or cut down.
+fn notify_limited(app_handle: &AppHandle, notice: CaptureLimited) {
+    log::info!("{}", notice.message);
+    let _ = app_handle.emit("capture-limited", notice);
+}
+
+/// Clipboard formats password managers set to mark a copy as secret.
+const CONCEALED_FORMATS: &[&str] = &[
+    "org.nspasteboard.ConcealedType",
+    "org.nspasteboard.TransientType",
+    "ExcludeClipboardContentFromMonitorProcessing",
+];
+
+/// Checks the current clipboard for concealed-content hints. KDE's
+/// `x-kde-passwordManagerHint` only counts when it carries `secret`, Windows'
+/// `CanIncludeInClipboardHistory` when it's set to zero.
+fn is_concealed_copy() -> bool {
+    let Ok(ctx) = ClipboardContext::new() else {
+        return false;
+    };
+    let Ok(formats) = ctx.available_formats() else {
+        return false;
+    };
+
+    formats.iter().any(|format| {
+        match format.as_str() {
+            "x-kde-passwordManagerHint" =>
+                ctx
+                    .get_buffer(format)
+                    .map(|value| value.starts_with(b"secret"))
+                    .unwrap_or(false),
+            "CanIncludeInClipboardHistory" =>
+                ctx
+                    .get_buffer(format)
+                    .map(|value| value.iter().all(|b| *b == 0))
+                    .unwrap_or(false),
+            _ => CONCEALED_FORMATS.contains(&format.as_str()),
+        }
+    })
+}
+
+/// Page URL browsers attach to a copy, from their private formats or the
+/// CF_HTML header on Windows.
+fn read_source_url() -> Option<String> {
+    let ctx = ClipboardContext::new().ok()?;
+    let formats = ctx.available_formats().ok()?;
+
+    formats
+        .iter()
+        .filter(|format| SOURCE_URL_FORMATS.contains(&format.as_str()))
+        .find_map(|format| ctx.get_buffer(format).ok().and_then(|bytes| window::parse_source_url(&bytes)))
+        .or_else(|| {
+            formats
+                .iter()
+                .find(|format| format.as_str() == HTML_FORMAT)
+                .and_then(|format| ctx.get_buffer(format).ok())
+                .and_then(|bytes| window::html_source_url(&String::from_utf8_lossy(&bytes)))
+        })
+}
+
+/// Reads the rich formats offered next to plain text, skipping any that fail
+/// to read or come back empty.
+fn read_representations(clipboard: &Clipboard, html: bool, rtf: bool) -> Vec<(RichFormat, String)> {
+    let mut representations = Vec::new();
+    if html {
+        if let Ok(data) = clipboard.read_html() {
+            if !data.trim().is_empty() {
+                representations.push((RichFormat::Html, data));
+            }
+        }
+    }
+    if rtf {
+        if let Ok(data) = clipboard.read_rtf() {
+            if !data.trim().is_empty() {
+                representations.push((RichFormat::Rtf, data));
+            }
+        }
+    }
+    representations
+}
+
 async fn get_pool(
     app_handle: &AppHandle
 ) -> Result<tauri::State<'_, SqlitePool>, Box<dyn std::error::Error + Send + Sync>> {
@@ -262,10 +634,12 @@ pub fn start_monitor(app_handle: AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+/// Stores a captured image along with its thumbnail and returns the path and,
+/// if the thumbnail could be made, the image's width and height.
 async fn save_image_to_file(
     app_handle: &AppHandle,
-    base64_data: &str
-) -> Result<String, Box<dyn std::error::Error>> {
+    bytes: &[u8]
+) -> Result<(String, Option<(u32, u32)>), Box<dyn std::error::Error>> {
     let app_data_dir = app_handle.path().app_data_dir().unwrap();
     let images_dir = app_data_dir.join("images");
     fs::create_dir_all(&images_dir)?;
@@ -273,8 +647,17 @@ async fn save_image_to_file(
     let file_name = format!("{}.png", Uuid::new_v4());
     let file_path = images_dir.join(&file_name);
 
-    let bytes = STANDARD.decode(base64_data)?;
-    fs::write(&file_path, bytes)?;
+    fs::write(&file_path, db::encryption::encrypt_bytes(bytes)?)?;
+    let file_path = file_path.to_string_lossy().into_owned();
+
+    // Not fatal, `read_thumbnail` makes missing thumbnails on demand
+    let dimensions = match db::history::store_thumbnail(&file_path, bytes) {
+        Ok(dimensions) => Some(dimensions),
+        Err(e) => {
+            eprintln!("Failed to create thumbnail: {}", e);
+            None
+        }
+    };
 
-    Ok(file_path.to_string_lossy().into_owned())
+    Ok((file_path, dimensions))
 }
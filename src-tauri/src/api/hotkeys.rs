@@ -1,3 +1,5 @@
+use crate::api::capture;
+use crate::db::settings::{ get_capture_pause_settings, CapturePauseSettings };
 use crate::utils::commands::center_window_on_current_monitor;
 use crate::utils::keys::KeyCode;
 use global_hotkey::{
@@ -16,6 +18,7 @@ use tauri_plugin_aptabase::EventTracker;
 struct HotkeyState {
     manager: Option<GlobalHotKeyManager>,
     registered_hotkey: Option<HotKey>,
+    pause_hotkey: Option<HotKey>,
 }
 
 unsafe impl Send for HotkeyState {}
@@ -44,6 +47,23 @@ pub fn setup(app_handle: tauri::AppHandle) {
         eprintln!("Error registering initial shortcut: {:?}", e);
     }
 
+    let pause_settings = rt.block_on(get_capture_pause_settings(&app_handle.state::<sqlx::SqlitePool>()));
+    if let Err(e) = register_pause_shortcut(&state, &pause_settings.keybind) {
+        eprintln!("Error registering pause shortcut: {:?}", e);
+    }
+
+    let state_clone = Arc::clone(&state);
+    app_handle.listen("update-pause-shortcut", move |event| {
+        let settings = serde_json
+            ::from_str::<String>(event.payload())
+            .ok()
+            .and_then(|value| serde_json::from_str::<CapturePauseSettings>(&value).ok())
+            .unwrap_or_default();
+        if let Err(e) = register_pause_shortcut(&state_clone, &settings.keybind) {
+            eprintln!("Error re-registering pause shortcut: {:?}", e);
+        }
+    });
+
     let state_clone = Arc::clone(&state);
     app_handle.listen("update-shortcut", move |event| {
         let payload_str = event.payload().replace("\\\"", "\"");
@@ -67,10 +87,10 @@ pub fn setup(app_handle: tauri::AppHandle) {
         }
     });
 
-    setup_hotkey_receiver(app_handle);
+    setup_hotkey_receiver(app_handle, state);
 }
 
-fn setup_hotkey_receiver(app_handle: AppHandle) {
+fn setup_hotkey_receiver(app_handle: AppHandle, state: Arc<Mutex<HotkeyState>>) {
     std::thread::spawn(move || {
         loop {
             match GlobalHotKeyEvent::receiver().recv() {
@@ -78,7 +98,15 @@ fn setup_hotkey_receiver(app_handle: AppHandle) {
                     if event.state == HotKeyState::Released {
                         continue;
                     }
-                    handle_hotkey_event(&app_handle);
+                    let is_pause = state
+                        .lock()
+                        .pause_hotkey.as_ref()
+                        .is_some_and(|hotkey| hotkey.id() == event.id);
+                    if is_pause {
+                        tauri::async_runtime::block_on(capture::toggle(&app_handle));
+                    } else {
+                        handle_hotkey_event(&app_handle);
+                    }
                 }
                 Err(e) => eprintln!("Error receiving hotkey event: {:?}", e),
             }
@@ -108,6 +136,31 @@ fn register_shortcut(state: &Arc<Mutex<HotkeyState>>, shortcut: &[String]) -> Re
     }
 }
 
+/// Replaces the pause shortcut, leaving it unset when `shortcut` is empty.
+fn register_pause_shortcut(
+    state: &Arc<Mutex<HotkeyState>>,
+    shortcut: &[String]
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut guard = state.lock();
+    let hotkey_state = &mut *guard;
+    if let (Some(old_hotkey), Some(manager)) = (hotkey_state.pause_hotkey.take(), &hotkey_state.manager) {
+        let _ = manager.unregister(old_hotkey);
+    }
+
+    if shortcut.is_empty() {
+        return Ok(());
+    }
+
+    let hotkey = parse_hotkey(shortcut)?;
+    if let Some(manager) = &hotkey_state.manager {
+        manager.register(hotkey)?;
+        hotkey_state.pause_hotkey = Some(hotkey);
+        Ok(())
+    } else {
+        Err("Hotkey manager not initialized".into())
+    }
+}
+
 fn parse_hotkey(shortcut: &[String]) -> Result<HotKey, Box<dyn std::error::Error>> {
     let mut modifiers = Modifiers::empty();
     let mut code = None;
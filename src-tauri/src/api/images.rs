@@ -0,0 +1,94 @@
+use crate::db::history::{ is_sensitive, load_image, SENSITIVE_ERROR };
+use crate::utils::types::ImageError;
+use sqlx::SqlitePool;
+use tauri::http::{ header, Request, Response, StatusCode };
+use tauri::{ AppHandle, Manager, Runtime, UriSchemeContext, UriSchemeResponder };
+
+/// Stored images are served as `qopy-image://localhost/<id>`, or
+/// `http://qopy-image.localhost/<id>` on Windows. `?variant=thumb` returns
+/// the thumbnail instead of the full image.
+pub const SCHEME: &str = "qopy-image";
+
+pub fn handle<R: Runtime>(
+    ctx: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder
+) {
+    let app_handle = ctx.app_handle().clone();
+    tauri::async_runtime::spawn(async move {
+        responder.respond(respond(&app_handle, &request).await);
+    });
+}
+
+async fn respond<R: Runtime>(app_handle: &AppHandle<R>, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let id = request.uri().path().trim_start_matches('/');
+    if id.is_empty() || id.contains('/') {
+        return error(StatusCode::BAD_REQUEST, "Expected an image id");
+    }
+
+    let Some(thumbnail) = parse_variant(request.uri().query()) else {
+        return error(StatusCode::BAD_REQUEST, "Unknown image variant");
+    };
+
+    let Some(pool) = app_handle.try_state::<SqlitePool>() else {
+        return error(StatusCode::SERVICE_UNAVAILABLE, "Database not ready");
+    };
+
+    // Like their text, sensitive images are only shown once revealed
+    match is_sensitive(&pool, id).await {
+        Ok(false) => {}
+        Ok(true) => {
+            return error(StatusCode::FORBIDDEN, SENSITIVE_ERROR);
+        }
+        Err(e) => {
+            return error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string());
+        }
+    }
+
+    match load_image(app_handle, &pool, id, thumbnail).await {
+        Ok(bytes) => {
+            let mime = image
+                ::guess_format(&bytes)
+                .map(|format| format.to_mime_type())
+                .unwrap_or("application/octet-stream");
+            Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, mime)
+                .header(header::CONTENT_LENGTH, bytes.len())
+                // Kept out of the webview's disk cache, the images may be private
+                .header(header::CACHE_CONTROL, "no-store")
+                .body(bytes)
+                .unwrap_or_else(|e| error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()))
+        }
+        Err(e) => {
+            let status = match e {
+                ImageError::NotFound | ImageError::MissingFile => StatusCode::NOT_FOUND,
+                ImageError::AccessDenied => StatusCode::FORBIDDEN,
+                ImageError::Failed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            error(status, &e.to_string())
+        }
+    }
+}
+
+/// `true` for the thumbnail, `None` for a variant that doesn't exist.
+fn parse_variant(query: Option<&str>) -> Option<bool> {
+    let variant = query
+        .unwrap_or_default()
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("variant="));
+
+    match variant {
+        None | Some("full") => Some(false),
+        Some("thumb") => Some(true),
+        Some(_) => None,
+    }
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain")
+        .body(message.as_bytes().to_vec())
+        .unwrap_or_default()
+}
@@ -1,4 +1,7 @@
+pub mod capture;
 pub mod clipboard;
 pub mod hotkeys;
+pub mod images;
+pub mod selection;
 pub mod tray;
 pub mod updater;
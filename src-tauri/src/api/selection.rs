@@ -0,0 +1,168 @@
+use crate::db::settings::{ get_primary_selection_settings, PrimarySelectionSettings };
+use lazy_static::lazy_static;
+use parking_lot::{ Mutex, RwLock };
+use sqlx::SqlitePool;
+use tauri::{ AppHandle, Listener, Manager };
+
+#[cfg(target_os = "linux")]
+use {
+    crate::api::{ capture, clipboard },
+    crate::db,
+    crate::utils::apps::get_app_info,
+    crate::utils::debounce::Debouncer,
+    crate::utils::types::{ HistoryItem, Selection },
+    crate::utils::window,
+    arboard::{ GetExtLinux, LinuxClipboardKind, SetExtLinux },
+    std::time::{ Duration, Instant },
+    tauri::Emitter,
+};
+
+/// How often PRIMARY is read while its capture is enabled.
+#[cfg(target_os = "linux")]
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+lazy_static! {
+    static ref SETTINGS: RwLock<PrimarySelectionSettings> = RwLock::new(
+        PrimarySelectionSettings::default()
+    );
+    /// Text the app put into PRIMARY itself, which isn't captured again
+    static ref WRITTEN: Mutex<Option<String>> = Mutex::new(None);
+}
+
+#[cfg(target_os = "linux")]
+lazy_static! {
+    /// Kept for the app's lifetime, on X11 a selection set through it is only
+    /// served while it exists
+    static ref PRIMARY: Mutex<Option<arboard::Clipboard>> = Mutex::new(arboard::Clipboard::new().ok());
+}
+
+/// Loads the settings and, on Linux, starts watching PRIMARY. Nothing is read
+/// until capture is enabled.
+pub fn setup(app_handle: AppHandle) {
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        *SETTINGS.write() = get_primary_selection_settings(&handle.state::<SqlitePool>()).await;
+    });
+
+    app_handle.listen("update-primary-selection", |event| {
+        *SETTINGS.write() = serde_json
+            ::from_str::<String>(event.payload())
+            .ok()
+            .and_then(|value| serde_json::from_str::<PrimarySelectionSettings>(&value).ok())
+            .unwrap_or_default();
+    });
+
+    #[cfg(target_os = "linux")]
+    std::thread::spawn(move || monitor(app_handle));
+}
+
+/// Puts `text` into PRIMARY so a middle click pastes it, without the monitor
+/// capturing it as a new selection.
+pub fn write_primary(text: String) -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    {
+        let mut primary = PRIMARY.lock();
+        let primary = primary.as_mut().ok_or("Primary selection is not available")?;
+        primary
+            .set()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text(text.clone())
+            .map_err(|e| e.to_string())?;
+        *WRITTEN.lock() = Some(text);
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = text;
+        Err("The primary selection is only available on Linux".to_string())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_primary() -> Option<String> {
+    PRIMARY.lock()
+        .as_mut()?
+        .get()
+        .clipboard(LinuxClipboardKind::Primary)
+        .text()
+        .ok()
+}
+
+/// Polls PRIMARY and captures a selection once it has stopped changing for
+/// the configured debounce time.
+#[cfg(target_os = "linux")]
+fn monitor(app_handle: AppHandle) {
+    let mut debouncer: Option<Debouncer<String>> = None;
+
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+
+        let settings = SETTINGS.read().clone();
+        if !settings.enabled {
+            debouncer = None;
+            continue;
+        }
+        let delay = Duration::from_millis(settings.debounce_ms);
+        let Some(text) = read_primary() else {
+            continue;
+        };
+
+        let debouncer = debouncer.get_or_insert_with(|| {
+            // Whatever was selected before capture was enabled isn't a new selection
+            let mut debouncer = Debouncer::new(delay);
+            debouncer.settle(text.clone());
+            debouncer
+        });
+        debouncer.set_delay(delay);
+
+        if WRITTEN.lock().as_deref() == Some(text.as_str()) {
+            debouncer.settle(text);
+            continue;
+        }
+
+        if let Some(text) = debouncer.observe(text, Instant::now()) {
+            let handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                capture_selection(&handle, text).await;
+            });
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+async fn capture_selection(app_handle: &AppHandle, text: String) {
+    if capture::is_paused() || text.trim().is_empty() {
+        return;
+    }
+
+    let (app_name, app_path, app_icon) = get_app_info();
+    let filter = db::settings::get_app_filter_setting(&app_handle.state::<SqlitePool>()).await;
+    if !filter.allows(&app_name) {
+        log::debug!("Skipping primary selection from excluded app {}", app_name);
+        return;
+    }
+
+    let window_title = window::active_window_title();
+    let prepare = move |mut item: HistoryItem| {
+        item.source_path = app_path.clone();
+        item.window_title = window_title.clone();
+        item.selection = Selection::Primary;
+        item
+    };
+
+    let _write = db::WRITE_LOCK.lock().await;
+    // Selections carry no rich formats worth keeping
+    let stored = clipboard::store_text(
+        app_handle,
+        app_handle.state::<SqlitePool>(),
+        text,
+        app_name,
+        app_icon,
+        prepare,
+        |_| Vec::new()
+    ).await;
+    if stored {
+        let _ = app_handle.emit("clipboard-content-updated", ());
+    }
+}
@@ -1,4 +1,15 @@
-use tauri::{ menu::{ MenuBuilder, MenuItemBuilder }, tray::TrayIconBuilder, Emitter, Manager };
+use crate::api::capture;
+use crate::utils::types::CaptureStatus;
+use chrono::Local;
+use tauri::{
+    image::Image,
+    menu::{ MenuBuilder, MenuItem, MenuItemBuilder },
+    tray::{ TrayIcon, TrayIconBuilder },
+    Emitter,
+    Listener,
+    Manager,
+    Wry,
+};
 use tauri_plugin_aptabase::EventTracker;
 
 pub fn setup(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
@@ -12,13 +23,17 @@ pub fn setup(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
     );
 
     let icon_bytes = include_bytes!("../../icons/Square71x71Logo.png");
-    let icon = tauri::image::Image::from_bytes(icon_bytes).unwrap();
+    let icon = Image::from_bytes(icon_bytes).unwrap();
+    let paused_icon = dimmed(&icon);
 
-    let _tray = TrayIconBuilder::new()
+    let pause_item = MenuItemBuilder::with_id("pause", "Pause Capture").build(app)?;
+
+    let tray = TrayIconBuilder::new()
         .menu(
             &MenuBuilder::new(app)
                 .items(&[&MenuItemBuilder::with_id("app_name", "Qopy").enabled(false).build(app)?])
                 .items(&[&MenuItemBuilder::with_id("show", "Show/Hide").build(app)?])
+                .items(&[&pause_item])
                 .items(&[&MenuItemBuilder::with_id("settings", "Settings").build(app)?])
                 .items(&[&MenuItemBuilder::with_id("quit", "Quit").build(app)?])
                 .build()?
@@ -47,6 +62,12 @@ pub fn setup(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
                     }
                     window.emit("main_route", ()).unwrap();
                 }
+                "pause" => {
+                    let app_handle = _app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        capture::toggle(&app_handle).await;
+                    });
+                }
                 "settings" => {
                     let _ = _app.track_event("tray_settings", None);
                     window.emit("settings", ()).unwrap();
@@ -54,8 +75,45 @@ pub fn setup(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
                 _ => (),
             }
         })
-        .icon(icon)
+        .icon(icon.clone())
+        .tooltip("Qopy")
         .build(app)?;
 
+    app.listen("capture-paused-changed", move |event| {
+        if let Ok(status) = serde_json::from_str::<CaptureStatus>(event.payload()) {
+            show_capture_status(&tray, &pause_item, &status, &icon, &paused_icon);
+        }
+    });
+
     Ok(())
 }
+
+fn show_capture_status(
+    tray: &TrayIcon,
+    pause_item: &MenuItem<Wry>,
+    status: &CaptureStatus,
+    icon: &Image<'static>,
+    paused_icon: &Image<'static>
+) {
+    let (label, tooltip) = match (status.paused, status.until) {
+        (false, _) => ("Pause Capture".to_string(), "Qopy".to_string()),
+        (true, None) => ("Resume Capture".to_string(), "Qopy (paused)".to_string()),
+        (true, Some(until)) => {
+            let until = until.with_timezone(&Local).format("%H:%M");
+            (format!("Resume Capture (paused until {})", until), format!("Qopy (paused until {})", until))
+        }
+    };
+
+    let _ = pause_item.set_text(label);
+    let _ = tray.set_tooltip(Some(tooltip));
+    let _ = tray.set_icon(Some(if status.paused { paused_icon.clone() } else { icon.clone() }));
+}
+
+/// The tray icon at reduced opacity, shown while capture is paused.
+fn dimmed(icon: &Image<'_>) -> Image<'static> {
+    let mut rgba = icon.rgba().to_vec();
+    for pixel in rgba.chunks_exact_mut(4) {
+        pixel[3] = ((pixel[3] as u16) * 2 / 5) as u8;
+    }
+    Image::new_owned(rgba, icon.width(), icon.height())
+}
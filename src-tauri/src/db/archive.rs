@@ -0,0 +1,306 @@
+use crate::db::encryption;
+use crate::db::WRITE_LOCK;
+use crate::db::history::{
+    decrypt_item,
+    get_representation,
+    insert_or_bump,
+    pin_item,
+    read_image_file,
+    row_to_item,
+    store_thumbnail,
+};
+use crate::utils::hash::{ hash_image, hash_text };
+use crate::utils::types::{ ContentType, HistoryItem, ImportReport, RichFormat };
+use chrono::{ DateTime, Utc };
+use serde::{ Deserialize, Serialize };
+use sqlx::{ Row, SqlitePool };
+use std::collections::HashMap;
+use std::fs::{ self, File };
+use std::io::{ Read, Write };
+use tauri::{ Emitter, Manager };
+use tauri_plugin_aptabase::EventTracker;
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+use zip::{ CompressionMethod, ZipArchive, ZipWriter };
+
+const ARCHIVE_VERSION: u32 = 1;
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Settings that only make sense on the machine they were created on.
+const LOCAL_SETTINGS: &[&str] = &["encryption", "capture_pause_state"];
+
+/// Layout of `manifest.json`. Images are stored next to it under `images/`
+/// and referenced through `blob`.
+#[derive(Deserialize, Serialize)]
+struct Manifest {
+    version: u32,
+    exported_at: DateTime<Utc>,
+    items: Vec<ArchiveItem>,
+    #[serde(default)]
+    settings: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ArchiveItem {
+    #[serde(flatten)]
+    item: HistoryItem,
+    blob: Option<String>,
+    #[serde(default)]
+    representations: HashMap<RichFormat, String>,
+}
+
+/// Writes the selected items (all of them when `ids` is `None`), their image
+/// files and optionally the settings into a zip archive at `path`. The
+/// archive is always written in plaintext, even from an encrypted history.
+#[tauri::command]
+pub async fn export_history(
+    app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    path: String,
+    ids: Option<Vec<String>>,
+    include_settings: Option<bool>
+) -> Result<usize, String> {
+    let rows = sqlx
+        ::query(
+            "SELECT h.id, h.source, COALESCE(s.icon, h.source_icon) AS source_icon, h.source_id, h.content_type, h.content, h.favicon, h.timestamp, h.language, h.window_title, h.source_url, h.kind, h.selection, h.pinned, h.sensitive, h.expires_at, h.width, h.height,
+                    (SELECT group_concat(format) FROM history_representations WHERE history_id = h.id) AS rich_formats
+             FROM history h
+             LEFT JOIN sources s ON s.id = h.source_id
+             WHERE h.sensitive = 0 ORDER BY h.timestamp DESC"
+        )
+        .fetch_all(&*pool).await
+        .map_err(|e| e.to_string())?;
+
+    let mut items = Vec::new();
+    for row in rows.iter() {
+        let item = decrypt_item(row_to_item(row))?;
+        if ids.as_ref().is_some_and(|ids| !ids.contains(&item.id)) {
+            continue;
+        }
+        items.push(item);
+    }
+
+    let mut settings = HashMap::new();
+    if include_settings.unwrap_or(true) {
+        let rows = sqlx
+            ::query("SELECT key, value FROM settings")
+            .fetch_all(&*pool).await
+            .map_err(|e| e.to_string())?;
+        for row in rows {
+            let key: String = row.get("key");
+            if !LOCAL_SETTINGS.contains(&key.as_str()) {
+                settings.insert(key, row.get("value"));
+            }
+        }
+    }
+
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(File::create(&path).map_err(|e| e.to_string())?);
+    let mut manifest = Manifest {
+        version: ARCHIVE_VERSION,
+        exported_at: Utc::now(),
+        items: Vec::with_capacity(items.len()),
+        settings,
+    };
+
+    for item in items {
+        let mut blob = None;
+        if item.content_type == ContentType::Image {
+            match read_image_file(&item.content) {
+                Ok(bytes) => {
+                    let name = format!("images/{}.png", item.id);
+                    zip.start_file(name.as_str(), options).map_err(|e| e.to_string())?;
+                    zip.write_all(&bytes).map_err(|e| e.to_string())?;
+                    blob = Some(name);
+                }
+                Err(e) => {
+                    eprintln!("Skipping image {} in export: {}", item.content, e);
+                    continue;
+                }
+            }
+        }
+
+        let mut representations = HashMap::new();
+        for format in item.rich_formats.iter() {
+            if let Some(data) = get_representation(&pool, &item.id, *format).await? {
+                representations.insert(*format, data);
+            }
+        }
+
+        manifest.items.push(ArchiveItem { item, blob, representations });
+    }
+
+    let exported = manifest.items.len();
+    zip.start_file(MANIFEST_NAME, options).map_err(|e| e.to_string())?;
+    let json = serde_json::to_vec(&manifest).map_err(|e| e.to_string())?;
+    zip.write_all(&json).map_err(|e| e.to_string())?;
+    zip.finish().map_err(|e| e.to_string())?;
+
+    let _ = app_handle.track_event(
+        "history_exported",
+        Some(serde_json::json!({
+        "count": exported
+    }))
+    );
+
+    Ok(exported)
+}
+
+/// Merges an archive written by `export_history` into the history. Items are
+/// matched by content hash like `add_history_item`: unknown items are added,
+/// known ones take the archive's timestamp and pin when those are newer, and
+/// everything else is skipped.
+#[tauri::command]
+pub async fn import_history(
+    app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    path: String,
+    import_settings: Option<bool>
+) -> Result<ImportReport, String> {
+    let file = File::open(&path).map_err(|e| e.to_string())?;
+    let mut archive = ZipArchive::new(file).map_err(|e| e.to_string())?;
+
+    let manifest: Manifest = {
+        let mut file = archive.by_name(MANIFEST_NAME).map_err(|e| e.to_string())?;
+        let mut json = String::new();
+        file.read_to_string(&mut json).map_err(|e| e.to_string())?;
+        serde_json::from_str(&json).map_err(|e| e.to_string())?
+    };
+
+    if manifest.version > ARCHIVE_VERSION {
+        return Err(format!("Unsupported archive version {}", manifest.version));
+    }
+
+    let images_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("images");
+    fs::create_dir_all(&images_dir).map_err(|e| e.to_string())?;
+
+    let _write = WRITE_LOCK.lock().await;
+    let mut report = ImportReport::default();
+
+    for ArchiveItem { mut item, blob, representations } in manifest.items {
+        let mut image = None;
+        let content_hash = if item.content_type == ContentType::Image {
+            let Some(bytes) = blob.as_deref().and_then(|name| read_blob(&mut archive, name)) else {
+                report.skipped += 1;
+                continue;
+            };
+            let Ok(hash) = hash_image(&bytes) else {
+                report.skipped += 1;
+                continue;
+            };
+            image = Some(bytes);
+            hash
+        } else {
+            hash_text(&item.content_type.to_string(), &item.content)
+        };
+
+        let existing = sqlx
+            ::query("SELECT id FROM history WHERE content_hash = ?")
+            .bind(encryption::protect_hash(content_hash.clone()))
+            .fetch_optional(&*pool).await
+            .map_err(|e| e.to_string())?;
+
+        if let Some(existing) = existing {
+            let id: String = existing.get("id");
+            let result = sqlx
+                ::query(
+                    "UPDATE history SET timestamp = ? WHERE id = ? AND julianday(timestamp) < julianday(?)"
+                )
+                .bind(item.timestamp)
+                .bind(&id)
+                .bind(item.timestamp)
+                .execute(&*pool).await
+                .map_err(|e| e.to_string())?;
+
+            let pinned = item.pinned && pin_item(&pool, &id).await.map_err(|e| e.to_string())?;
+            if result.rows_affected() > 0 || pinned {
+                report.updated += 1;
+            } else {
+                report.skipped += 1;
+            }
+            continue;
+        }
+
+        // Fresh ids so items from another machine can't collide with local ones
+        item.id = Uuid::new_v4().to_string();
+        if let Some(bytes) = image {
+            let file_path = images_dir.join(format!("{}.png", Uuid::new_v4()));
+            fs::write(&file_path, encryption::encrypt_bytes(&bytes)?).map_err(|e| e.to_string())?;
+            item.content = file_path.to_string_lossy().into_owned();
+            match store_thumbnail(&item.content, &bytes) {
+                Ok((width, height)) => {
+                    item.width = Some(width);
+                    item.height = Some(height);
+                }
+                Err(e) => eprintln!("Failed to create thumbnail: {}", e),
+            }
+        }
+        item.content_hash = Some(content_hash);
+        item.representations = representations.into_iter().collect();
+
+        if insert_or_bump(&pool, &item).await? {
+            if item.pinned {
+                pin_item(&pool, &item.id).await.map_err(|e| e.to_string())?;
+            }
+            report.added += 1;
+        } else {
+            report.skipped += 1;
+        }
+    }
+
+    if import_settings.unwrap_or(true) {
+        for (key, value) in manifest.settings {
+            if LOCAL_SETTINGS.contains(&key.as_str()) {
+                continue;
+            }
+            sqlx
+                ::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
+                .bind(&key)
+                .bind(&value)
+                .execute(&*pool).await
+                .map_err(|e| e.to_string())?;
+
+            match key.as_str() {
+                "keybind" => {
+                    let _ = app_handle.emit("update-shortcut", &value);
+                }
+                "retention" => {
+                    let _ = app_handle.emit("update-retention", &value);
+                }
+                "capture_pause" => {
+                    let _ = app_handle.emit("update-pause-shortcut", &value);
+                }
+                "capture_debounce" => {
+                    let _ = app_handle.emit("update-capture-debounce", &value);
+                }
+                "primary_selection" => {
+                    let _ = app_handle.emit("update-primary-selection", &value);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let _ = app_handle.emit("clipboard-content-updated", ());
+    let _ = app_handle.track_event(
+        "history_imported",
+        Some(serde_json::json!({
+        "added": report.added,
+        "updated": report.updated,
+        "skipped": report.skipped
+    }))
+    );
+
+    Ok(report)
+}
+
+fn read_blob(archive: &mut ZipArchive<File>, name: &str) -> Option<Vec<u8>> {
+    let mut file = archive.by_name(name).ok()?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+    Some(bytes)
+}
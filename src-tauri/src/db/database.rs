@@ -33,6 +33,10 @@ pub fn setup(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
 
     rt.block_on(async {
         apply_migrations(&pool).await?;
+        super::encryption::setup(app.handle(), &pool).await;
+        if let Err(e) = super::history::backfill_content_hashes(app.handle(), &pool).await {
+            eprintln!("Failed to backfill content hashes: {}", e);
+        }
         if is_new_db {
             if let Err(e) = super::history::initialize_history(&pool).await {
                 eprintln!("Failed to initialize history: {}", e);
@@ -44,6 +48,15 @@ pub fn setup(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
         Ok::<(), Box<dyn std::error::Error>>(())
     })?;
 
+    // Decoding every stored image takes a while, so don't hold up startup for it
+    tauri::async_runtime::spawn(async move {
+        match super::history::backfill_thumbnails(&pool).await {
+            Ok(0) => {}
+            Ok(created) => println!("Created {} missing thumbnails", created),
+            Err(e) => eprintln!("Failed to backfill thumbnails: {}", e),
+        }
+    });
+
     Ok(())
 }
 
@@ -83,15 +96,9 @@ async fn apply_migrations(pool: &SqlitePool) -> Result<(), Box<dyn std::error::E
 
     for (version, content) in migration_files {
         if version > current_version {
-            let statements: Vec<&str> = content
-                .split(';')
-                .map(|s| s.trim())
-                .filter(|s| !s.is_empty())
-                .collect();
-
-            for statement in statements {
+            for statement in split_statements(content) {
                 sqlx
-                    ::query(statement)
+                    ::query(&statement)
                     .execute(pool).await
                     .map_err(|e| format!("Failed to execute migration {}: {}", version, e))?;
             }
@@ -105,3 +112,35 @@ async fn apply_migrations(pool: &SqlitePool) -> Result<(), Box<dyn std::error::E
 
     Ok(())
 }
+
+/// Splits a migration file into statements, keeping trigger bodies
+/// (`BEGIN ... END;`) together since they contain semicolons themselves.
+fn split_statements(content: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+
+    for part in content.split(';') {
+        if !current.is_empty() {
+            current.push(';');
+        }
+        current.push_str(part);
+
+        let trimmed = current.trim();
+        let upper = trimmed.to_uppercase();
+        if upper.starts_with("CREATE TRIGGER") && !upper.ends_with("END") {
+            continue;
+        }
+
+        if !trimmed.is_empty() {
+            statements.push(trimmed.to_string());
+        }
+        current.clear();
+    }
+
+    let trimmed = current.trim();
+    if !trimmed.is_empty() {
+        statements.push(trimmed.to_string());
+    }
+
+    statements
+}
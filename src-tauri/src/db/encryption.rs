@@ -0,0 +1,491 @@
+use crate::db::settings::{ get_encryption_config, EncryptionConfig, KeySource };
+use crate::db::WRITE_LOCK;
+use crate::utils::hash::{ hash_image, hash_text };
+use crate::utils::thumbnail::thumbnail_path;
+use aes_gcm::{ aead::{ Aead, KeyInit }, Aes256Gcm, Nonce };
+use argon2::Argon2;
+use base64::{ engine::general_purpose::STANDARD, Engine };
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use rand::{ rng, Rng };
+use serde::Serialize;
+use sha2::{ Digest, Sha256 };
+use sqlx::{ Row, SqlitePool };
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{ AtomicBool, Ordering };
+use tauri::{ AppHandle, Emitter, Manager };
+use tauri_plugin_aptabase::EventTracker;
+
+/// Prefix of encrypted column values: `enc:v1:` + base64(nonce || ciphertext)
+const FIELD_PREFIX: &str = "enc:v1:";
+/// Header of encrypted image files, followed by nonce || ciphertext
+const FILE_MAGIC: &[u8] = b"QOPYENC1";
+const NONCE_LEN: usize = 12;
+const KEY_FILE: &str = "history.key";
+const VERIFIER: &[u8] = b"qopy-history";
+
+pub const LOCKED_ERROR: &str = "History is locked";
+
+type Key = [u8; 32];
+
+#[derive(Serialize)]
+pub struct EncryptionStatus {
+    pub enabled: bool,
+    pub unlocked: bool,
+    pub key_source: KeySource,
+}
+
+lazy_static! {
+    static ref ENABLED: AtomicBool = AtomicBool::new(false);
+    static ref SESSION_KEY: RwLock<Option<Key>> = RwLock::new(None);
+}
+
+/// Loads the stored configuration and, for key file mode, unlocks the session
+/// right away. Passphrase mode stays locked until `unlock_history` is called.
+pub async fn setup(app_handle: &AppHandle, pool: &SqlitePool) {
+    let config = get_encryption_config(pool).await;
+    ENABLED.store(config.enabled, Ordering::SeqCst);
+
+    if config.enabled && config.key_source == KeySource::KeyFile {
+        match read_key_file(app_handle).and_then(|key| verify_key(&config, &key).map(|_| key)) {
+            Ok(key) => {
+                *SESSION_KEY.write() = Some(key);
+            }
+            Err(e) => eprintln!("Failed to unlock history with key file: {}", e),
+        }
+    }
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+pub fn is_unlocked() -> bool {
+    SESSION_KEY.read().is_some()
+}
+
+/// Fails while the history is encrypted and locked.
+pub fn ensure_unlocked() -> Result<(), String> {
+    session_key().map(|_| ())
+}
+
+/// Encrypts a column value with the session key. Passes plaintext through when
+/// encryption is off and fails while the history is locked.
+pub fn encrypt_field(value: &str) -> Result<String, String> {
+    match session_key()? {
+        Some(key) => Ok(format!("{}{}", FIELD_PREFIX, STANDARD.encode(seal(&key, value.as_bytes())?))),
+        None => Ok(value.to_string()),
+    }
+}
+
+pub fn encrypt_optional(value: Option<String>) -> Result<Option<String>, String> {
+    value.map(|v| encrypt_field(&v)).transpose()
+}
+
+/// Decrypts a column value. Values without the prefix are returned as is, so
+/// rows written before encryption was enabled stay readable.
+pub fn decrypt_field(value: &str) -> Result<String, String> {
+    let Some(encoded) = value.strip_prefix(FIELD_PREFIX) else {
+        return Ok(value.to_string());
+    };
+    let key = (*SESSION_KEY.read()).ok_or_else(|| LOCKED_ERROR.to_string())?;
+    let sealed = STANDARD.decode(encoded).map_err(|e| e.to_string())?;
+    String::from_utf8(open(&key, &sealed)?).map_err(|e| e.to_string())
+}
+
+pub fn decrypt_optional(value: Option<String>) -> Result<Option<String>, String> {
+    value.map(|v| decrypt_field(&v)).transpose()
+}
+
+/// Encrypts the contents of an image file before it's written to disk.
+pub fn encrypt_bytes(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    match session_key()? {
+        Some(key) => Ok([FILE_MAGIC, seal(&key, bytes)?.as_slice()].concat()),
+        None => Ok(bytes.to_vec()),
+    }
+}
+
+/// Decrypts image file contents, plaintext files are returned unchanged.
+pub fn decrypt_bytes(bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+    let Some(sealed) = bytes.strip_prefix(FILE_MAGIC) else {
+        return Ok(bytes);
+    };
+    let key = (*SESSION_KEY.read()).ok_or_else(|| LOCKED_ERROR.to_string())?;
+    open(&key, sealed)
+}
+
+/// Keys content hashes with the session key so the dedup index doesn't let
+/// anyone confirm guesses about encrypted content.
+pub fn protect_hash(hash: String) -> String {
+    match *SESSION_KEY.read() {
+        Some(key) if is_enabled() => keyed_hash(&key, &hash),
+        _ => hash,
+    }
+}
+
+#[tauri::command]
+pub async fn get_encryption_status(
+    pool: tauri::State<'_, SqlitePool>
+) -> Result<EncryptionStatus, String> {
+    let config = get_encryption_config(&pool).await;
+    Ok(EncryptionStatus {
+        enabled: config.enabled,
+        unlocked: is_unlocked(),
+        key_source: config.key_source,
+    })
+}
+
+#[tauri::command]
+pub async fn unlock_history(
+    app_handle: AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    passphrase: Option<String>
+) -> Result<(), String> {
+    let config = get_encryption_config(&pool).await;
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let key = match (&config.key_source, passphrase) {
+        (KeySource::Passphrase, Some(passphrase)) => derive_key(&passphrase, &config)?,
+        (KeySource::Passphrase, None) => {
+            return Err("A passphrase is required".to_string());
+        }
+        (KeySource::KeyFile, _) => read_key_file(&app_handle)?,
+    };
+    verify_key(&config, &key)?;
+
+    *SESSION_KEY.write() = Some(key);
+    let _ = app_handle.emit("clipboard-content-updated", ());
+
+    Ok(())
+}
+
+/// Encrypts the existing history. Without a passphrase a random key is
+/// generated and kept in `app_data_dir/history.key`.
+#[tauri::command]
+pub async fn enable_encryption(
+    app_handle: AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    passphrase: Option<String>
+) -> Result<(), String> {
+    if is_enabled() {
+        return Err("Encryption is already enabled".to_string());
+    }
+
+    let (key, config) = new_key(passphrase)?;
+    rewrite_history(&app_handle, &pool, Some(key), config).await?;
+
+    let _ = app_handle.track_event("encryption_enabled", None);
+
+    Ok(())
+}
+
+/// Re-encrypts the history under a new key. Requires an unlocked session.
+#[tauri::command]
+pub async fn rotate_encryption_key(
+    app_handle: AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    passphrase: Option<String>
+) -> Result<(), String> {
+    if !is_enabled() {
+        return Err("Encryption is not enabled".to_string());
+    }
+    session_key()?;
+
+    let (key, config) = new_key(passphrase)?;
+    rewrite_history(&app_handle, &pool, Some(key), config).await?;
+
+    let _ = app_handle.track_event("encryption_key_rotated", None);
+
+    Ok(())
+}
+
+/// Decrypts the history back to plaintext. Requires an unlocked session.
+#[tauri::command]
+pub async fn disable_encryption(
+    app_handle: AppHandle,
+    pool: tauri::State<'_, SqlitePool>
+) -> Result<(), String> {
+    if !is_enabled() {
+        return Ok(());
+    }
+    session_key()?;
+
+    rewrite_history(&app_handle, &pool, None, EncryptionConfig::default()).await?;
+
+    let _ = app_handle.track_event("encryption_disabled", None);
+
+    Ok(())
+}
+
+/// Rewrites every row, image file and thumbnail from the current session key to `to`
+/// (`None` meaning plaintext) and stores `config` in the same transaction.
+/// Image files are staged next to the originals and only swapped in once the
+/// database commit went through.
+async fn rewrite_history(
+    app_handle: &AppHandle,
+    pool: &SqlitePool,
+    to: Option<Key>,
+    config: EncryptionConfig
+) -> Result<(), String> {
+    // Held until the new key is in place, a row written in between would be
+    // left under the old one
+    let _write = WRITE_LOCK.lock().await;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let rows = sqlx
+        ::query("SELECT id, content_type, content, favicon, source_icon, window_title, source_url FROM history")
+        .fetch_all(&mut *tx).await
+        .map_err(|e| e.to_string())?;
+
+    let seal_field = |value: String| -> Result<String, String> {
+        match &to {
+            Some(key) => Ok(format!("{}{}", FIELD_PREFIX, STANDARD.encode(seal(key, value.as_bytes())?))),
+            None => Ok(value),
+        }
+    };
+
+    let mut staged_files: Vec<(PathBuf, PathBuf)> = Vec::new();
+
+    for row in rows.iter() {
+        let id: String = row.get("id");
+        let content_type: String = row.get("content_type");
+        let content = decrypt_field(&row.get::<String, _>("content"))?;
+        let favicon = decrypt_optional(row.get("favicon"))?;
+        let source_icon = decrypt_optional(row.get("source_icon"))?;
+        let window_title = decrypt_optional(row.get("window_title"))?;
+        let source_url = decrypt_optional(row.get("source_url"))?;
+
+        let mut content_hash = hash_text(&content_type, &content);
+        if content_type == "image" {
+            let image = PathBuf::from(&content);
+            for path in [image.clone(), thumbnail_path(&content)] {
+                let Ok(bytes) = fs::read(&path) else {
+                    continue;
+                };
+                let bytes = decrypt_bytes(bytes)?;
+                if path == image {
+                    if let Ok(hash) = hash_image(&bytes) {
+                        content_hash = hash;
+                    }
+                }
+
+                let staged = path.with_extension("png.rekey");
+                let sealed = match &to {
+                    Some(key) => [FILE_MAGIC, seal(key, &bytes)?.as_slice()].concat(),
+                    None => bytes,
+                };
+                fs::write(&staged, sealed).map_err(|e| e.to_string())?;
+                staged_files.push((staged, path));
+            }
+        }
+
+        sqlx
+            ::query(
+                "UPDATE history SET content = ?, favicon = ?, source_icon = ?, window_title = ?, source_url = ?, content_hash = ? WHERE id = ?"
+            )
+            .bind(seal_field(content)?)
+            .bind(favicon.map(seal_field).transpose()?)
+            .bind(source_icon.map(seal_field).transpose()?)
+            .bind(window_title.map(seal_field).transpose()?)
+            .bind(source_url.map(seal_field).transpose()?)
+            .bind(match &to {
+                Some(key) => keyed_hash(key, &content_hash),
+                None => content_hash,
+            })
+            .bind(id)
+            .execute(&mut *tx).await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let sources = sqlx
+        ::query("SELECT id, icon FROM sources WHERE icon IS NOT NULL")
+        .fetch_all(&mut *tx).await
+        .map_err(|e| e.to_string())?;
+
+    for row in sources.iter() {
+        let icon = decrypt_field(&row.get::<String, _>("icon"))?;
+        sqlx
+            ::query("UPDATE sources SET icon = ? WHERE id = ?")
+            .bind(seal_field(icon)?)
+            .bind(row.get::<i64, _>("id"))
+            .execute(&mut *tx).await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let representations = sqlx
+        ::query("SELECT history_id, format, data FROM history_representations")
+        .fetch_all(&mut *tx).await
+        .map_err(|e| e.to_string())?;
+
+    for row in representations.iter() {
+        let data = decrypt_field(&row.get::<String, _>("data"))?;
+        sqlx
+            ::query("UPDATE history_representations SET data = ? WHERE history_id = ? AND format = ?")
+            .bind(seal_field(data)?)
+            .bind(row.get::<String, _>("history_id"))
+            .bind(row.get::<String, _>("format"))
+            .execute(&mut *tx).await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let config_json = serde_json::to_string(&config).map_err(|e| e.to_string())?;
+    sqlx
+        ::query("INSERT OR REPLACE INTO settings (key, value) VALUES ('encryption', ?)")
+        .bind(config_json)
+        .execute(&mut *tx).await
+        .map_err(|e| e.to_string())?;
+
+    let key_file = key_file_path(app_handle)?;
+    if let (Some(key), KeySource::KeyFile) = (&to, &config.key_source) {
+        let staged = key_file.with_extension("key.new");
+        fs::write(&staged, STANDARD.encode(key)).map_err(|e| e.to_string())?;
+        staged_files.push((staged, key_file.clone()));
+    }
+
+    if let Err(e) = tx.commit().await {
+        for (staged, _) in staged_files {
+            let _ = fs::remove_file(staged);
+        }
+        return Err(e.to_string());
+    }
+
+    for (staged, path) in staged_files {
+        if let Err(e) = fs::rename(&staged, &path) {
+            eprintln!("Failed to replace {}: {}", path.display(), e);
+        }
+    }
+
+    if config.key_source != KeySource::KeyFile || to.is_none() {
+        let _ = fs::remove_file(&key_file);
+    }
+
+    ENABLED.store(config.enabled, Ordering::SeqCst);
+    *SESSION_KEY.write() = to;
+
+    // Drop plaintext left behind in the search index and in freed pages
+    scrub_database(pool).await.map_err(|e| e.to_string())?;
+    let _ = app_handle.emit("clipboard-content-updated", ());
+
+    Ok(())
+}
+
+/// Empties the search index, vacuums the database and reindexes the current
+/// column values. VACUUM may renumber rowids, which the index is keyed on.
+async fn scrub_database(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO history_fts (history_fts) VALUES ('delete-all')").execute(pool).await?;
+    sqlx::query("VACUUM").execute(pool).await?;
+    sqlx
+        ::query(
+            "INSERT INTO history_fts (rowid, content, window_title, source_url)
+             SELECT rowid, CASE WHEN content_type = 'image' THEN NULL ELSE content END, window_title, source_url
+             FROM history"
+        )
+        .execute(pool).await?;
+    Ok(())
+}
+
+/// Returns the session key when encryption is on, `None` when it's off and an
+/// error while the history is locked.
+fn session_key() -> Result<Option<Key>, String> {
+    if !is_enabled() {
+        return Ok(None);
+    }
+    (*SESSION_KEY.read()).map(Some).ok_or_else(|| LOCKED_ERROR.to_string())
+}
+
+fn new_key(passphrase: Option<String>) -> Result<(Key, EncryptionConfig), String> {
+    let mut config = EncryptionConfig {
+        enabled: true,
+        ..Default::default()
+    };
+
+    let key = match passphrase {
+        Some(passphrase) => {
+            if passphrase.is_empty() {
+                return Err("The passphrase must not be empty".to_string());
+            }
+            let mut salt = [0u8; 16];
+            rng().fill(&mut salt);
+            config.key_source = KeySource::Passphrase;
+            config.salt = Some(STANDARD.encode(salt));
+            derive_key(&passphrase, &config)?
+        }
+        None => {
+            let mut key = [0u8; 32];
+            rng().fill(&mut key);
+            config.key_source = KeySource::KeyFile;
+            key
+        }
+    };
+
+    config.verifier = Some(STANDARD.encode(seal(&key, VERIFIER)?));
+    Ok((key, config))
+}
+
+fn derive_key(passphrase: &str, config: &EncryptionConfig) -> Result<Key, String> {
+    let salt = config.salt
+        .as_deref()
+        .ok_or_else(|| "Missing key salt".to_string())
+        .and_then(|salt| STANDARD.decode(salt).map_err(|e| e.to_string()))?;
+
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+fn verify_key(config: &EncryptionConfig, key: &Key) -> Result<(), String> {
+    let verifier = config.verifier
+        .as_deref()
+        .ok_or_else(|| "Missing key verifier".to_string())
+        .and_then(|v| STANDARD.decode(v).map_err(|e| e.to_string()))?;
+
+    match open(key, &verifier) {
+        Ok(plaintext) if plaintext == VERIFIER => Ok(()),
+        _ => Err("Wrong passphrase or key".to_string()),
+    }
+}
+
+fn key_file_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app_handle.path().app_data_dir().map_err(|e| e.to_string())?.join(KEY_FILE))
+}
+
+fn read_key_file(app_handle: &AppHandle) -> Result<Key, String> {
+    let encoded = fs::read_to_string(key_file_path(app_handle)?).map_err(|e| e.to_string())?;
+    STANDARD.decode(encoded.trim())
+        .map_err(|e| e.to_string())?
+        .try_into()
+        .map_err(|_| "Invalid key file".to_string())
+}
+
+fn keyed_hash(key: &Key, hash: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(key);
+    hasher.update(hash.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn seal(key: &Key, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|e| e.to_string())?;
+    let mut nonce = [0u8; NONCE_LEN];
+    rng().fill(&mut nonce);
+
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| "Encryption failed".to_string())?;
+    Ok([&nonce[..], ciphertext.as_slice()].concat())
+}
+
+fn open(key: &Key, sealed: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() < NONCE_LEN {
+        return Err("Encrypted value is truncated".to_string());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|e| e.to_string())?;
+
+    cipher.decrypt(Nonce::from_slice(nonce), ciphertext).map_err(|_| "Decryption failed".to_string())
+}
@@ -1,11 +1,55 @@
-use crate::utils::types::{ ContentType, HistoryItem };
+use crate::db::encryption;
+use crate::db::sources::upsert_source;
+use crate::utils::color::{ Color, ColorFormat };
+use crate::utils::hash::{ hash_image, hash_text };
+use crate::utils::thumbnail;
+use crate::utils::types::{
+    ContentKind,
+    ContentType,
+    HistoryItem,
+    ImageError,
+    ImageSweepReport,
+    InfoCode,
+    InfoColor,
+    InfoFile,
+    InfoImage,
+    InfoLink,
+    InfoText,
+    ItemInfo,
+    LanguageCount,
+    RichFormat,
+    SearchResult,
+    Selection,
+};
 use base64::{ engine::general_purpose::STANDARD, Engine };
 use rand::{ rng, Rng };
 use rand::distr::Alphanumeric;
-use sqlx::{ Row, SqlitePool };
+use sqlx::{ sqlite::SqliteRow, Row, SqlitePool };
+use std::collections::HashSet;
 use std::fs;
+use std::io::Cursor;
+use std::path::{ Component, Path, PathBuf };
+use std::time::Duration;
+use tauri::Manager;
 use tauri_plugin_aptabase::EventTracker;
 
+/// Default and maximum number of search results.
+const SEARCH_LIMIT: i64 = 100;
+
+const SENSITIVE_MASK: &str = "••••••••";
+
+pub const SENSITIVE_ERROR: &str = "Reveal the item to see its content";
+
+const ORPHAN_GRACE_PERIOD: Duration = Duration::from_secs(5 * 60);
+
+/// Age in days at which an item's relevance score is halved.
+const RECENCY_HALF_LIFE_DAYS: f64 = 30.0;
+
+/// Control characters wrapping matched terms in search snippets, chosen so
+/// they can never collide with markup the frontend has to escape.
+const SNIPPET_MATCH_START: &str = "\u{2}";
+const SNIPPET_MATCH_END: &str = "\u{3}";
+
 pub async fn initialize_history(pool: &SqlitePool) -> Result<(), Box<dyn std::error::Error>> {
     let id: String = rng()
         .sample_iter(&Alphanumeric)
@@ -26,27 +70,26 @@ pub async fn initialize_history(pool: &SqlitePool) -> Result<(), Box<dyn std::er
 }
 
 #[tauri::command]
-pub async fn get_history(pool: tauri::State<'_, SqlitePool>) -> Result<Vec<HistoryItem>, String> {
+pub async fn get_history(
+    pool: tauri::State<'_, SqlitePool>,
+    pinned_first: Option<bool>
+) -> Result<Vec<HistoryItem>, String> {
     let rows = sqlx
         ::query(
-            "SELECT id, source, source_icon, content_type, content, favicon, timestamp, language FROM history ORDER BY timestamp DESC"
+            &format!(
+                "SELECT id, source, source_icon, source_id, content_type, content, favicon, timestamp, language, window_title, source_url, kind, selection, pinned, sensitive, expires_at, width, height,
+                        (SELECT group_concat(format) FROM history_representations WHERE history_id = history.id) AS rich_formats
+                 FROM history ORDER BY {}",
+                history_order(pinned_first)
+            )
         )
         .fetch_all(&*pool).await
         .map_err(|e| e.to_string())?;
 
     let items = rows
         .iter()
-        .map(|row| HistoryItem {
-            id: row.get("id"),
-            source: row.get("source"),
-            source_icon: row.get("source_icon"),
-            content_type: ContentType::from(row.get::<String, _>("content_type")),
-            content: row.get("content"),
-            favicon: row.get("favicon"),
-            timestamp: row.get("timestamp"),
-            language: row.get("language"),
-        })
-        .collect();
+        .map(|row| decrypt_item(row_to_item(row)).map(mask_sensitive))
+        .collect::<Result<_, _>>()?;
 
     Ok(items)
 }
@@ -57,51 +100,241 @@ pub async fn add_history_item(
     pool: tauri::State<'_, SqlitePool>,
     item: HistoryItem
 ) -> Result<(), String> {
-    let (id, source, source_icon, content_type, content, favicon, timestamp, language) =
-        item.to_row();
+    insert_or_bump(&pool, &item).await?;
 
-    let existing = sqlx
-        ::query("SELECT id FROM history WHERE content = ? AND content_type = ?")
-        .bind(&content)
-        .bind(&content_type)
-        .fetch_optional(&*pool).await
-        .map_err(|e| e.to_string())?;
+    let _ = app_handle.track_event(
+        "history_item_added",
+        Some(serde_json::json!({
+        "content_type": item.content_type.to_string()
+    }))
+    );
 
-    match existing {
-        Some(_) => {
+    Ok(())
+}
+
+/// Inserts `item`, or moves the row with the same content hash to the top of
+/// the history instead. Returns `true` when a new row was inserted.
+pub async fn insert_or_bump(pool: &SqlitePool, item: &HistoryItem) -> Result<bool, String> {
+    let content_hash = item.content_hash.clone().unwrap_or_else(|| compute_content_hash(item));
+    let source_id = upsert_source(
+        pool,
+        &item.source,
+        item.source_path.as_deref(),
+        item.source_icon.clone(),
+        item.timestamp
+    ).await?;
+
+    if bump_by_hash(pool, &content_hash).await.map_err(|e| e.to_string())? {
+        // The latest copy decides where the item came from
+        sqlx
+            ::query("UPDATE history SET selection = ? WHERE content_hash = ?")
+            .bind(item.selection.to_string())
+            .bind(encryption::protect_hash(content_hash.clone()))
+            .execute(pool).await
+            .map_err(|e| e.to_string())?;
+        if item.sensitive {
             sqlx
                 ::query(
-                    "UPDATE history SET timestamp = strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now') WHERE content = ? AND content_type = ?"
+                    "UPDATE history SET sensitive = 1, expires_at = ? WHERE content_hash = ? AND pinned = 0"
                 )
-                .bind(&content)
-                .bind(&content_type)
-                .execute(&*pool).await
+                .bind(item.expires_at)
+                .bind(encryption::protect_hash(content_hash.clone()))
+                .execute(pool).await
                 .map_err(|e| e.to_string())?;
-        }
-        None => {
             sqlx
                 ::query(
-                    "INSERT INTO history (id, source, source_icon, content_type, content, favicon, timestamp, language) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+                    "DELETE FROM history_representations WHERE history_id IN (SELECT id FROM history WHERE content_hash = ? AND sensitive = 1)"
                 )
-                .bind(id)
-                .bind(source)
-                .bind(source_icon)
-                .bind(content_type)
-                .bind(content)
-                .bind(favicon)
-                .bind(timestamp)
-                .bind(language)
-                .execute(&*pool).await
+                .bind(encryption::protect_hash(content_hash.clone()))
+                .execute(pool).await
+                .map_err(|e| e.to_string())?;
+        } else if !item.representations.is_empty() {
+            let id: Option<String> = sqlx
+                ::query_scalar("SELECT id FROM history WHERE content_hash = ? AND sensitive = 0")
+                .bind(encryption::protect_hash(content_hash))
+                .fetch_optional(pool).await
                 .map_err(|e| e.to_string())?;
+            if let Some(id) = id {
+                save_representations(pool, &id, &item.representations).await?;
+            }
         }
+        return Ok(false);
     }
 
-    let _ = app_handle.track_event(
-        "history_item_added",
-        Some(serde_json::json!({
-        "content_type": item.content_type.to_string()
-    }))
-    );
+    // The icon lives in `sources`, not on every row
+    let (id, source, _, content_type, content, favicon, timestamp, language) = item.to_row();
+
+    sqlx
+        ::query(
+            "INSERT INTO history (id, source, source_id, content_type, content, favicon, timestamp, language, window_title, source_url, kind, selection, content_hash, sensitive, expires_at, width, height) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(id)
+        .bind(source)
+        .bind(source_id)
+        .bind(content_type)
+        .bind(encryption::encrypt_field(&content)?)
+        .bind(encryption::encrypt_optional(favicon)?)
+        .bind(timestamp)
+        .bind(language)
+        .bind(encryption::encrypt_optional(item.window_title.clone())?)
+        .bind(encryption::encrypt_optional(item.source_url.clone())?)
+        .bind(item.kind.map(|kind| kind.to_string()))
+        .bind(item.selection.to_string())
+        .bind(encryption::protect_hash(content_hash))
+        .bind(item.sensitive)
+        .bind(item.expires_at)
+        .bind(item.width)
+        .bind(item.height)
+        .execute(pool).await
+        .map_err(|e| e.to_string())?;
+
+    // The rich formats of a secret would be readable without revealing it
+    if !item.sensitive {
+        save_representations(pool, &item.id, &item.representations).await?;
+    }
+
+    Ok(true)
+}
+
+async fn save_representations(
+    pool: &SqlitePool,
+    id: &str,
+    representations: &[(RichFormat, String)]
+) -> Result<(), String> {
+    for (format, data) in representations {
+        sqlx
+            ::query(
+                "INSERT OR REPLACE INTO history_representations (history_id, format, data) VALUES (?, ?, ?)"
+            )
+            .bind(id)
+            .bind(format.to_string())
+            .bind(encryption::encrypt_field(data)?)
+            .execute(pool).await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Returns the stored rich representation of an item in `format`, if any.
+pub async fn get_representation(
+    pool: &SqlitePool,
+    id: &str,
+    format: RichFormat
+) -> Result<Option<String>, String> {
+    let data: Option<String> = sqlx
+        ::query_scalar("SELECT data FROM history_representations WHERE history_id = ? AND format = ?")
+        .bind(id)
+        .bind(format.to_string())
+        .fetch_optional(pool).await
+        .map_err(|e| e.to_string())?;
+
+    data.map(|d| encryption::decrypt_field(&d)).transpose()
+}
+
+#[tauri::command]
+pub async fn get_rich_content(
+    pool: tauri::State<'_, SqlitePool>,
+    id: String,
+    format: RichFormat
+) -> Result<Option<String>, String> {
+    refuse_sensitive(&pool, &id).await?;
+
+    get_representation(&pool, &id, format).await
+}
+
+/// Whether the item with `id` is marked sensitive, `false` if it doesn't exist.
+pub async fn is_sensitive(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let sensitive: Option<bool> = sqlx
+        ::query_scalar("SELECT sensitive FROM history WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool).await?;
+
+    Ok(sensitive.unwrap_or(false))
+}
+
+/// Errors for a sensitive item, commands that hand out content derived from
+/// it call this so it stays masked until `reveal_history_item`.
+pub async fn refuse_sensitive(pool: &SqlitePool, id: &str) -> Result<(), String> {
+    if is_sensitive(pool, id).await.map_err(|e| e.to_string())? {
+        return Err(SENSITIVE_ERROR.to_string());
+    }
+    Ok(())
+}
+
+/// Moves the item with `content_hash` to the top of the history. Returns
+/// `false` if no such item exists.
+pub async fn bump_by_hash(pool: &SqlitePool, content_hash: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx
+        ::query(
+            "UPDATE history SET timestamp = strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now') WHERE content_hash = ?"
+        )
+        .bind(encryption::protect_hash(content_hash.to_string()))
+        .execute(pool).await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+fn compute_content_hash(item: &HistoryItem) -> String {
+    if item.content_type == ContentType::Image {
+        let hash = read_image_file(&item.content)
+            .ok()
+            .and_then(|bytes| hash_image(&bytes).ok());
+        if let Some(hash) = hash {
+            return hash;
+        }
+    }
+    hash_text(&item.content_type.to_string(), &item.content)
+}
+
+/// Fills `content_hash` for rows created before it existed. Pinned rows are
+/// visited first, then newest first, so of a set of duplicates the pinned or
+/// most recent copy is kept and the rest are dropped.
+pub async fn backfill_content_hashes(
+    app_handle: &tauri::AppHandle,
+    pool: &SqlitePool
+) -> Result<(), sqlx::Error> {
+    let rows = sqlx
+        ::query(
+            "SELECT id, source, source_icon, source_id, content_type, content, favicon, timestamp, language, pinned
+             FROM history WHERE content_hash IS NULL ORDER BY pinned DESC, timestamp DESC"
+        )
+        .fetch_all(pool).await?;
+
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let mut duplicate_images = Vec::new();
+    let mut tx = pool.begin().await?;
+
+    for row in rows.iter() {
+        let item = row_to_item(row);
+        let content_hash = compute_content_hash(&item);
+
+        let taken: Option<String> = sqlx
+            ::query_scalar("SELECT id FROM history WHERE content_hash = ?")
+            .bind(encryption::protect_hash(content_hash.clone()))
+            .fetch_optional(&mut *tx).await?;
+
+        if taken.is_some() {
+            if !item.pinned {
+                sqlx::query("DELETE FROM history WHERE id = ?").bind(&item.id).execute(&mut *tx).await?;
+                if item.content_type == ContentType::Image {
+                    duplicate_images.push(item.content);
+                }
+            }
+            continue;
+        }
+
+        sqlx
+            ::query("UPDATE history SET content_hash = ? WHERE id = ?")
+            .bind(encryption::protect_hash(content_hash))
+            .bind(&item.id)
+            .execute(&mut *tx).await?;
+    }
+
+    tx.commit().await?;
+    remove_image_files(app_handle, duplicate_images);
 
     Ok(())
 }
@@ -109,52 +342,241 @@ pub async fn add_history_item(
 #[tauri::command]
 pub async fn search_history(
     pool: tauri::State<'_, SqlitePool>,
-    query: String
-) -> Result<Vec<HistoryItem>, String> {
-    if query.trim().is_empty() {
-        return Ok(Vec::new());
+    query: String,
+    limit: Option<i64>,
+    language: Option<String>,
+    kind: Option<ContentKind>,
+    window_title: Option<String>,
+    url: Option<String>
+) -> Result<Vec<SearchResult>, String> {
+    let limit = limit.unwrap_or(SEARCH_LIMIT).clamp(1, SEARCH_LIMIT);
+    let non_empty = |value: Option<String>| value.filter(|value| !value.trim().is_empty());
+    let filter = SearchFilter {
+        language: non_empty(language),
+        kind: kind.map(|kind| kind.to_string()),
+        window_title: non_empty(window_title),
+        url: non_empty(url),
+    };
+
+    // The index only holds ciphertext once encryption is on
+    if encryption::is_enabled() {
+        return search_decrypted(&pool, &query, &filter, limit).await;
+    }
+
+    let fts_query = filter.match_query(&query);
+    if fts_query.is_empty() {
+        // A filter on its own lists the matching items
+        if filter.is_empty() {
+            return Ok(Vec::new());
+        }
+        return search_decrypted(&pool, "", &filter, limit).await;
     }
 
-    let query = format!("%{}%", query);
-    
     let rows = sqlx
         ::query(
-            "SELECT id, source, source_icon, content_type, content, favicon, timestamp, language 
-             FROM history 
-             WHERE content LIKE ? 
-             ORDER BY timestamp DESC
-             LIMIT 100"
+            "SELECT h.id, h.source, h.source_icon, h.source_id, h.content_type, h.content, h.favicon, h.timestamp, h.language, h.window_title, h.source_url, h.kind, h.selection, h.pinned, h.sensitive, h.expires_at, h.width, h.height,
+                    (SELECT group_concat(format) FROM history_representations WHERE history_id = h.id) AS rich_formats,
+                    CASE WHEN h.content_type = 'image' THEN NULL
+                         ELSE snippet(history_fts, 0, ?, ?, '…', 16) END AS snippet
+             FROM history_fts
+             JOIN history h ON h.rowid = history_fts.rowid
+             WHERE history_fts MATCH ?
+               AND (? IS NULL OR h.language = ? COLLATE NOCASE)
+               AND (? IS NULL OR h.kind = ?)
+             ORDER BY bm25(history_fts, 1.0, 0.5, 0.5) / (1.0 + (julianday('now') - julianday(h.timestamp)) / ?)
+             LIMIT ?"
         )
-        .bind(query)
+        .bind(SNIPPET_MATCH_START)
+        .bind(SNIPPET_MATCH_END)
+        .bind(fts_query)
+        .bind(&filter.language)
+        .bind(&filter.language)
+        .bind(&filter.kind)
+        .bind(&filter.kind)
+        .bind(RECENCY_HALF_LIFE_DAYS)
+        .bind(limit)
         .fetch_all(&*pool).await
         .map_err(|e| e.to_string())?;
 
-    let mut items = Vec::with_capacity(rows.len());
+    let items = rows
+        .iter()
+        .map(|row| {
+            let item = decrypt_item(row_to_item(row))?;
+            Ok(SearchResult {
+                snippet: if item.sensitive { None } else { row.get("snippet") },
+                item: mask_sensitive(item),
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok(items)
+}
+
+/// Optional restrictions of `search_history` on top of the query.
+struct SearchFilter {
+    language: Option<String>,
+    kind: Option<String>,
+    /// Terms that have to occur in the source window title
+    window_title: Option<String>,
+    /// Terms that have to occur in the source URL
+    url: Option<String>,
+}
+
+impl SearchFilter {
+    fn is_empty(&self) -> bool {
+        self.language.is_none() &&
+            self.kind.is_none() &&
+            self.window_title.is_none() &&
+            self.url.is_none()
+    }
+
+    /// The MATCH expression for `query` with the filters applied. The query
+    /// is parenthesized, FTS5 binds AND tighter than OR.
+    fn match_query(&self, query: &str) -> String {
+        [build_fts_query(query).map(|query| format!("({})", query)), self.fts_query()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    }
+
+    /// The title and URL filters as FTS5 column filters.
+    fn fts_query(&self) -> Option<String> {
+        let column = |name: &str, value: &Option<String>| {
+            value
+                .as_deref()
+                .and_then(build_fts_query)
+                .map(|query| format!("{} : ({})", name, query))
+        };
+        let parts: Vec<String> = [
+            column("window_title", &self.window_title),
+            column("source_url", &self.url),
+        ]
+            .into_iter()
+            .flatten()
+            .collect();
+
+        if parts.is_empty() { None } else { Some(parts.join(" AND ")) }
+    }
+
+    /// Substring check of the title and URL filters against a decrypted item.
+    fn matches_context(&self, item: &HistoryItem) -> bool {
+        let contains = |field: &Option<String>, filter: &Option<String>| {
+            let Some(filter) = filter else {
+                return true;
+            };
+            let field = field.as_deref().unwrap_or_default().to_lowercase();
+            filter
+                .split_whitespace()
+                .all(|term| field.contains(&term.trim_matches('"').to_lowercase()))
+        };
+        contains(&item.window_title, &self.window_title) && contains(&item.source_url, &self.url)
+    }
+}
+
+/// Substring search used while the history is encrypted and for filter only
+/// listings. Every whitespace separated term has to occur in the decrypted
+/// content, window title or URL, without terms only the filter applies.
+/// Images only match through their title and URL.
+async fn search_decrypted(
+    pool: &SqlitePool,
+    query: &str,
+    filter: &SearchFilter,
+    limit: i64
+) -> Result<Vec<SearchResult>, String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|term| term.trim_matches('"').to_lowercase())
+        .filter(|term| !term.is_empty())
+        .collect();
+    if terms.is_empty() && filter.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let rows = sqlx
+        ::query(
+            "SELECT id, source, source_icon, source_id, content_type, content, favicon, timestamp, language, window_title, source_url, kind, selection, pinned, sensitive, expires_at, width, height,
+                    (SELECT group_concat(format) FROM history_representations WHERE history_id = history.id) AS rich_formats
+             FROM history
+             WHERE (? IS NULL OR language = ? COLLATE NOCASE)
+               AND (? IS NULL OR kind = ?)
+             ORDER BY timestamp DESC"
+        )
+        .bind(&filter.language)
+        .bind(&filter.language)
+        .bind(&filter.kind)
+        .bind(&filter.kind)
+        .fetch_all(pool).await
+        .map_err(|e| e.to_string())?;
+
+    let mut items = Vec::new();
     for row in rows.iter() {
-        items.push(HistoryItem {
-            id: row.get("id"),
-            source: row.get("source"),
-            source_icon: row.get("source_icon"),
-            content_type: ContentType::from(row.get::<String, _>("content_type")),
-            content: row.get("content"),
-            favicon: row.get("favicon"),
-            timestamp: row.get("timestamp"),
-            language: row.get("language"),
-        });
+        let item = decrypt_item(row_to_item(row))?;
+        if !filter.matches_context(&item) {
+            continue;
+        }
+
+        let searchable = [
+            (item.content_type != ContentType::Image).then_some(item.content.as_str()),
+            item.window_title.as_deref(),
+            item.source_url.as_deref(),
+        ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join("\n")
+            .to_lowercase();
+        if terms.iter().all(|term| searchable.contains(term.as_str())) {
+            items.push(SearchResult { item: mask_sensitive(item), snippet: None });
+            if (items.len() as i64) >= limit {
+                break;
+            }
+        }
     }
 
     Ok(items)
 }
 
+/// Languages of the classified code snippets, most common first.
+#[tauri::command]
+pub async fn get_history_languages(
+    pool: tauri::State<'_, SqlitePool>
+) -> Result<Vec<LanguageCount>, String> {
+    let rows = sqlx
+        ::query(
+            "SELECT language, COUNT(*) AS count FROM history
+             WHERE language IS NOT NULL
+             GROUP BY language ORDER BY count DESC, language"
+        )
+        .fetch_all(&*pool).await
+        .map_err(|e| e.to_string())?;
+
+    Ok(
+        rows
+            .iter()
+            .map(|row| LanguageCount {
+                language: row.get("language"),
+                count: row.get("count"),
+            })
+            .collect()
+    )
+}
+
 #[tauri::command]
 pub async fn load_history_chunk(
     pool: tauri::State<'_, SqlitePool>,
     offset: i64,
-    limit: i64
+    limit: i64,
+    pinned_first: Option<bool>
 ) -> Result<Vec<HistoryItem>, String> {
     let rows = sqlx
         ::query(
-            "SELECT id, source, source_icon, content_type, content, favicon, timestamp, language FROM history ORDER BY timestamp DESC LIMIT ? OFFSET ?"
+            &format!(
+                "SELECT id, source, source_icon, source_id, content_type, content, favicon, timestamp, language, window_title, source_url, kind, selection, pinned, sensitive, expires_at, width, height,
+                        (SELECT group_concat(format) FROM history_representations WHERE history_id = history.id) AS rich_formats
+                 FROM history ORDER BY {} LIMIT ? OFFSET ?",
+                history_order(pinned_first)
+            )
         )
         .bind(limit)
         .bind(offset)
@@ -163,17 +585,8 @@ pub async fn load_history_chunk(
 
     let items = rows
         .iter()
-        .map(|row| HistoryItem {
-            id: row.get("id"),
-            source: row.get("source"),
-            source_icon: row.get("source_icon"),
-            content_type: ContentType::from(row.get::<String, _>("content_type")),
-            content: row.get("content"),
-            favicon: row.get("favicon"),
-            timestamp: row.get("timestamp"),
-            language: row.get("language"),
-        })
-        .collect();
+        .map(|row| decrypt_item(row_to_item(row)).map(mask_sensitive))
+        .collect::<Result<_, _>>()?;
 
     Ok(items)
 }
@@ -184,11 +597,19 @@ pub async fn delete_history_item(
     pool: tauri::State<'_, SqlitePool>,
     id: String
 ) -> Result<(), String> {
-    sqlx
-        ::query("DELETE FROM history WHERE id = ?")
+    // Image paths can't be resolved while the history is locked
+    encryption::ensure_unlocked()?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let rows = sqlx
+        ::query("DELETE FROM history WHERE id = ? RETURNING content_type, content")
         .bind(id)
-        .execute(&*pool).await
+        .fetch_all(&mut *tx).await
         .map_err(|e| e.to_string())?;
+    let paths = image_paths(&rows)?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    remove_image_files(&app_handle, paths);
 
     let _ = app_handle.track_event("history_item_deleted", None);
 
@@ -199,19 +620,850 @@ pub async fn delete_history_item(
 pub async fn clear_history(
     app_handle: tauri::AppHandle,
     pool: tauri::State<'_, SqlitePool>
+) -> Result<(), String> {
+    encryption::ensure_unlocked()?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let rows = sqlx
+        ::query("DELETE FROM history WHERE pinned = 0 RETURNING content_type, content")
+        .fetch_all(&mut *tx).await
+        .map_err(|e| e.to_string())?;
+    let paths = image_paths(&rows)?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    remove_image_files(&app_handle, paths);
+
+    let _ = app_handle.track_event("history_cleared", None);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn pin_history_item(
+    app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    id: String
+) -> Result<(), String> {
+    pin_item(&pool, &id).await.map_err(|e| e.to_string())?;
+
+    let _ = app_handle.track_event("history_item_pinned", None);
+
+    Ok(())
+}
+
+/// Pins an item at the end of the pinned list. Returns `false` if it was
+/// already pinned or doesn't exist.
+pub async fn pin_item(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx
+        ::query(
+            "UPDATE history SET pinned = 1, expires_at = NULL,
+                pin_order = (SELECT COALESCE(MAX(pin_order), -1) + 1 FROM history WHERE pinned = 1)
+             WHERE id = ? AND pinned = 0"
+        )
+        .bind(id)
+        .execute(pool).await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+#[tauri::command]
+pub async fn unpin_history_item(
+    app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    id: String
 ) -> Result<(), String> {
     sqlx
-        ::query("DELETE FROM history")
+        ::query("UPDATE history SET pinned = 0, pin_order = NULL WHERE id = ?")
+        .bind(id)
         .execute(&*pool).await
         .map_err(|e| e.to_string())?;
 
-    let _ = app_handle.track_event("history_cleared", None);
+    let _ = app_handle.track_event("history_item_unpinned", None);
+
+    Ok(())
+}
+
+/// Rewrites the order of pinned items to match `ids`. Pinned items missing
+/// from `ids` keep their relative order after the listed ones.
+#[tauri::command]
+pub async fn reorder_pinned_items(
+    pool: tauri::State<'_, SqlitePool>,
+    ids: Vec<String>
+) -> Result<(), String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let offset = ids.len() as i64;
+    sqlx
+        ::query("UPDATE history SET pin_order = pin_order + ? WHERE pinned = 1")
+        .bind(offset)
+        .execute(&mut *tx).await
+        .map_err(|e| e.to_string())?;
+
+    for (position, id) in ids.iter().enumerate() {
+        sqlx
+            ::query("UPDATE history SET pin_order = ? WHERE id = ? AND pinned = 1")
+            .bind(position as i64)
+            .bind(id)
+            .execute(&mut *tx).await
+            .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
 
     Ok(())
 }
 
+/// Reconciles `app_data_dir/images` against the history table and removes
+/// files no row references. With `dry_run` nothing is deleted and the report
+/// lists what would have been removed.
+#[tauri::command]
+pub async fn sweep_orphaned_images(
+    app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    dry_run: bool
+) -> Result<ImageSweepReport, String> {
+    let report = sweep_images(&app_handle, &pool, dry_run).await?;
+
+    if !dry_run && !report.files.is_empty() {
+        let _ = app_handle.track_event(
+            "orphaned_images_removed",
+            Some(serde_json::json!({
+            "count": report.files.len()
+        }))
+        );
+    }
+
+    Ok(report)
+}
+
+pub async fn sweep_images(
+    app_handle: &tauri::AppHandle,
+    pool: &SqlitePool,
+    dry_run: bool
+) -> Result<ImageSweepReport, String> {
+    let images_dir = images_dir(app_handle)?;
+
+    let mut report = ImageSweepReport::default();
+    if !images_dir.exists() {
+        return Ok(report);
+    }
+
+    // A locked history can't tell which files are referenced, so don't touch any
+    let contents = sqlx
+        ::query_scalar::<_, String>("SELECT content FROM history WHERE content_type = 'image'")
+        .fetch_all(pool).await
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|content| encryption::decrypt_field(content))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let referenced: HashSet<String> = contents
+        .iter()
+        .flat_map(|content| [PathBuf::from(content), thumbnail::thumbnail_path(content)])
+        .filter_map(|path| path.file_name()?.to_str().map(str::to_string))
+        .collect();
+
+    for entry in fs::read_dir(&images_dir).map_err(|e| e.to_string())?.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() || referenced.contains(&*entry.file_name().to_string_lossy()) {
+            continue;
+        }
+
+        // Captures write the file before inserting the row, leave fresh files alone
+        let is_recent = metadata
+            .modified()
+            .ok()
+            .and_then(|m| m.elapsed().ok())
+            .is_some_and(|age| age < ORPHAN_GRACE_PERIOD);
+        if is_recent {
+            continue;
+        }
+
+        let path = entry.path();
+        if !dry_run {
+            if let Err(e) = fs::remove_file(&path) {
+                eprintln!("Failed to remove orphaned image {}: {}", path.display(), e);
+                continue;
+            }
+        }
+
+        report.bytes += metadata.len();
+        report.files.push(path.to_string_lossy().into_owned());
+    }
+
+    Ok(report)
+}
+
+/// Returns the unmasked content of a sensitive item.
+#[tauri::command]
+pub async fn reveal_history_item(
+    app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    id: String
+) -> Result<String, String> {
+    let content = get_content(&pool, &id).await?.ok_or_else(|| "Item not found".to_string())?;
+
+    let _ = app_handle.track_event("history_item_revealed", None);
+
+    Ok(content)
+}
+
+/// Metadata shown in the info panel, computed from the stored content.
+#[tauri::command]
+pub async fn get_item_info(pool: tauri::State<'_, SqlitePool>, id: String) -> Result<ItemInfo, String> {
+    let row = sqlx
+        ::query(
+            "SELECT id, source, source_icon, source_id, content_type, content, favicon, timestamp, language, window_title, source_url, kind, selection, pinned, sensitive, expires_at, width, height
+             FROM history WHERE id = ?"
+        )
+        .bind(&id)
+        .fetch_optional(&*pool).await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Item not found".to_string())?;
+
+    let item = decrypt_item(row_to_item(&row))?;
+    let sensitive = item.sensitive;
+    let info = item_info(item)?;
+    Ok(if sensitive { mask_info(info) } else { info })
+}
+
+fn item_info(item: HistoryItem) -> Result<ItemInfo, String> {
+    let HistoryItem { source, content_type, content, language, timestamp: copied, .. } = item;
+    let count = |n: usize| i32::try_from(n).unwrap_or(i32::MAX);
+
+    let info = match content_type {
+        ContentType::Text =>
+            ItemInfo::Text(InfoText {
+                source,
+                characters: count(content.chars().count()),
+                words: count(content.split_whitespace().count()),
+                copied,
+            }),
+        ContentType::Image => {
+            let bytes = read_image_file(&content)?;
+            let (width, height) = image::ImageReader
+                ::new(Cursor::new(&bytes))
+                .with_guessed_format()
+                .map_err(|e| e.to_string())?
+                .into_dimensions()
+                .map_err(|e| e.to_string())?;
+            ItemInfo::Image(InfoImage {
+                source,
+                dimensions: format!("{}x{}", width, height),
+                size: bytes.len() as i64,
+                copied,
+            })
+        }
+        ContentType::File => {
+            let metadata = fs::metadata(&content).ok();
+            ItemInfo::File(InfoFile {
+                source,
+                filesize: metadata.as_ref().map_or(0, |m| m.len() as i64),
+                exists: metadata.is_some(),
+                path: content,
+                copied,
+            })
+        }
+        ContentType::Link =>
+            ItemInfo::Link(InfoLink {
+                source,
+                title: None,
+                characters: count(content.chars().count()),
+                url: content,
+                copied,
+            }),
+        ContentType::Color => {
+            let color = Color::parse(&content);
+            let formatted = |format| color.and_then(|c| c.format(format)).unwrap_or_default();
+            ItemInfo::Color(InfoColor {
+                source,
+                hex: color
+                    .and_then(|c| c.format(ColorFormat::Hex))
+                    .unwrap_or_else(|| content.clone()),
+                rgb: formatted(ColorFormat::Rgb),
+                hsl: formatted(ColorFormat::Hsl),
+                copied,
+            })
+        }
+        ContentType::Code =>
+            ItemInfo::Code(InfoCode {
+                source,
+                language: language.unwrap_or_else(|| "Unknown".to_string()),
+                lines: count(content.lines().count()),
+                copied,
+            }),
+    };
+
+    Ok(info)
+}
+
+/// Hides whatever in `info` repeats the content of a sensitive item, the
+/// counts and sizes are kept.
+fn mask_info(info: ItemInfo) -> ItemInfo {
+    match info {
+        ItemInfo::File(info) => ItemInfo::File(InfoFile { path: SENSITIVE_MASK.to_string(), ..info }),
+        ItemInfo::Link(info) =>
+            ItemInfo::Link(InfoLink { url: SENSITIVE_MASK.to_string(), title: None, ..info }),
+        ItemInfo::Color(info) =>
+            ItemInfo::Color(InfoColor {
+                hex: SENSITIVE_MASK.to_string(),
+                rgb: SENSITIVE_MASK.to_string(),
+                hsl: SENSITIVE_MASK.to_string(),
+                ..info
+            }),
+        info => info,
+    }
+}
+
+/// Decrypted content of an item, `None` if the id doesn't exist.
+pub async fn get_content(pool: &SqlitePool, id: &str) -> Result<Option<String>, String> {
+    let content: Option<String> = sqlx
+        ::query_scalar("SELECT content FROM history WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool).await
+        .map_err(|e| e.to_string())?;
+
+    content.map(|c| encryption::decrypt_field(&c)).transpose()
+}
+
+/// Deletes items whose `expires_at` has passed and returns how many were
+/// removed. Waits while the history is locked, their images couldn't be
+/// found.
+pub async fn purge_expired(app_handle: &tauri::AppHandle, pool: &SqlitePool) -> Result<usize, String> {
+    if encryption::ensure_unlocked().is_err() {
+        return Ok(0);
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let rows = sqlx
+        ::query(
+            "DELETE FROM history
+             WHERE expires_at IS NOT NULL AND julianday(expires_at) <= julianday('now')
+             RETURNING content_type, content"
+        )
+        .fetch_all(&mut *tx).await
+        .map_err(|e| e.to_string())?;
+    let paths = image_paths(&rows)?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    remove_image_files(app_handle, paths);
+
+    Ok(rows.len())
+}
+
 #[tauri::command]
-pub async fn read_image(filename: String) -> Result<String, String> {
-    let bytes = fs::read(filename).map_err(|e| e.to_string())?;
+pub async fn read_image(
+    app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    id: String
+) -> Result<String, ImageError> {
+    let bytes = load_image(&app_handle, &pool, &id, false).await?;
     Ok(STANDARD.encode(bytes))
 }
+
+/// Bytes of an image item, or of its thumbnail. The path comes from the
+/// item's row and has to resolve inside the images directory. A missing
+/// thumbnail is made on the spot.
+pub async fn load_image<R: tauri::Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    pool: &SqlitePool,
+    id: &str,
+    thumbnail: bool
+) -> Result<Vec<u8>, ImageError> {
+    let images_dir = images_dir(app_handle).map_err(ImageError::Failed)?;
+
+    let content: String = sqlx
+        ::query_scalar("SELECT content FROM history WHERE id = ? AND content_type = 'image'")
+        .bind(id)
+        .fetch_optional(pool).await
+        .map_err(|e| ImageError::Failed(e.to_string()))?
+        .ok_or(ImageError::NotFound)?;
+    let stored = encryption::decrypt_field(&content).map_err(ImageError::Failed)?;
+    let path = resolve_image_path(&images_dir, Path::new(&stored))?;
+
+    if !thumbnail {
+        return read_image_file(&path.to_string_lossy()).map_err(ImageError::Failed);
+    }
+
+    match resolve_image_path(&images_dir, &thumbnail::thumbnail_path(&stored)) {
+        Ok(thumbnail_path) => {
+            return read_image_file(&thumbnail_path.to_string_lossy()).map_err(ImageError::Failed);
+        }
+        Err(ImageError::MissingFile) => {}
+        Err(e) => {
+            return Err(e);
+        }
+    }
+
+    let path = path.to_string_lossy();
+    let bytes = read_image_file(&path).map_err(ImageError::Failed)?;
+    let (width, height) = store_thumbnail(&path, &bytes).map_err(ImageError::Failed)?;
+    set_dimensions(pool, id, width, height).await.map_err(|e| ImageError::Failed(e.to_string()))?;
+
+    read_image_file(&thumbnail::thumbnail_path(&path).to_string_lossy()).map_err(ImageError::Failed)
+}
+
+/// `app_data_dir/images`, where captured images and their thumbnails are kept.
+pub fn images_dir<R: tauri::Runtime>(app_handle: &tauri::AppHandle<R>) -> Result<PathBuf, String> {
+    Ok(app_handle.path().app_data_dir().map_err(|e| e.to_string())?.join("images"))
+}
+
+/// Canonicalizes a stored image path and refuses anything that ends up
+/// outside `images_dir`, so `..` segments, symlinks or a tampered `content`
+/// column can't reach other files. Paths that don't exist are checked
+/// lexically, so the error doesn't reveal whether a file outside exists.
+pub fn resolve_image_path(images_dir: &Path, path: &Path) -> Result<PathBuf, ImageError> {
+    let canonical_dir = images_dir.canonicalize().map_err(|_| ImageError::MissingFile)?;
+
+    match path.canonicalize() {
+        Ok(path) if path.starts_with(&canonical_dir) && path != canonical_dir => Ok(path),
+        Ok(_) => Err(ImageError::AccessDenied),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let inside =
+                path.is_absolute() &&
+                normalize(path)
+                    .parent()
+                    .is_some_and(|parent| {
+                        parent.starts_with(&canonical_dir) || parent.starts_with(normalize(images_dir))
+                    });
+            Err(if inside { ImageError::MissingFile } else { ImageError::AccessDenied })
+        }
+        Err(e) => Err(ImageError::Failed(e.to_string())),
+    }
+}
+
+/// Resolves `.` and `..` without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Writes the thumbnail for the image stored at `path` and returns the
+/// image's width and height.
+pub fn store_thumbnail(path: &str, bytes: &[u8]) -> Result<(u32, u32), String> {
+    let (thumbnail, width, height) = thumbnail::make_thumbnail(bytes)?;
+    fs::write(thumbnail::thumbnail_path(path), encryption::encrypt_bytes(&thumbnail)?).map_err(|e|
+        e.to_string()
+    )?;
+    Ok((width, height))
+}
+
+async fn set_dimensions(pool: &SqlitePool, id: &str, width: u32, height: u32) -> Result<(), sqlx::Error> {
+    sqlx
+        ::query("UPDATE history SET width = ?, height = ? WHERE id = ?")
+        .bind(width)
+        .bind(height)
+        .bind(id)
+        .execute(pool).await?;
+    Ok(())
+}
+
+/// Creates thumbnails for image items captured before they existed. Runs in
+/// the background since decoding large screenshots takes a while, and skips
+/// everything while an encrypted history is locked.
+pub async fn backfill_thumbnails(pool: &SqlitePool) -> Result<usize, sqlx::Error> {
+    let rows = sqlx
+        ::query(
+            "SELECT id, content FROM history WHERE content_type = 'image' AND width IS NULL ORDER BY timestamp DESC"
+        )
+        .fetch_all(pool).await?;
+
+    let mut created = 0;
+    for row in rows.iter() {
+        let id: String = row.get("id");
+        let Ok(path) = encryption::decrypt_field(&row.get::<String, _>("content")) else {
+            continue;
+        };
+
+        let stored = tauri::async_runtime
+            ::spawn_blocking(move || {
+                let bytes = read_image_file(&path)?;
+                store_thumbnail(&path, &bytes)
+            }).await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result);
+
+        match stored {
+            Ok((width, height)) => {
+                set_dimensions(pool, &id, width, height).await?;
+                created += 1;
+            }
+            Err(e) => eprintln!("Failed to create thumbnail for {}: {}", id, e),
+        }
+    }
+
+    Ok(created)
+}
+
+pub fn row_to_item(row: &SqliteRow) -> HistoryItem {
+    HistoryItem {
+        id: row.get("id"),
+        source: row.get("source"),
+        source_icon: row.get("source_icon"),
+        source_id: row.try_get("source_id").ok().flatten(),
+        source_path: None,
+        content_type: ContentType::from(row.get::<String, _>("content_type")),
+        content: row.get("content"),
+        favicon: row.get("favicon"),
+        timestamp: row.get("timestamp"),
+        language: row.get("language"),
+        window_title: row.try_get("window_title").ok().flatten(),
+        source_url: row.try_get("source_url").ok().flatten(),
+        kind: row
+            .try_get::<Option<String>, _>("kind")
+            .ok()
+            .flatten()
+            .and_then(|kind| ContentKind::parse(&kind)),
+        selection: row
+            .try_get::<String, _>("selection")
+            .ok()
+            .and_then(|selection| Selection::parse(&selection))
+            .unwrap_or_default(),
+        pinned: row.get("pinned"),
+        content_hash: None,
+        sensitive: row.try_get("sensitive").unwrap_or(false),
+        expires_at: row.try_get("expires_at").ok().flatten(),
+        width: row.try_get("width").ok().flatten(),
+        height: row.try_get("height").ok().flatten(),
+        rich_formats: row
+            .try_get::<Option<String>, _>("rich_formats")
+            .ok()
+            .flatten()
+            .map(|formats| formats.split(',').filter_map(RichFormat::parse).collect())
+            .unwrap_or_default(),
+        representations: Vec::new(),
+    }
+}
+
+/// Paths of the image rows among `rows`. Fails rather than skip a row it
+/// can't decrypt, its files would be left on disk.
+fn image_paths(rows: &[SqliteRow]) -> Result<Vec<String>, String> {
+    rows.iter()
+        .filter(|row| row.get::<String, _>("content_type") == "image")
+        .map(|row| encryption::decrypt_field(&row.get::<String, _>("content")))
+        .collect()
+}
+
+/// Hides the content of sensitive items, `reveal_history_item` returns it.
+fn mask_sensitive(mut item: HistoryItem) -> HistoryItem {
+    if item.sensitive {
+        item.content = SENSITIVE_MASK.to_string();
+        item.rich_formats.clear();
+    }
+    item
+}
+
+/// Decrypts the encrypted columns of an item read from the database.
+pub fn decrypt_item(mut item: HistoryItem) -> Result<HistoryItem, String> {
+    item.content = encryption::decrypt_field(&item.content)?;
+    item.favicon = encryption::decrypt_optional(item.favicon)?;
+    item.source_icon = encryption::decrypt_optional(item.source_icon)?;
+    item.window_title = encryption::decrypt_optional(item.window_title)?;
+    item.source_url = encryption::decrypt_optional(item.source_url)?;
+    Ok(item)
+}
+
+/// Reads a stored image, decrypting it if it was written encrypted.
+pub fn read_image_file(path: &str) -> Result<Vec<u8>, String> {
+    encryption::decrypt_bytes(fs::read(path).map_err(|e| e.to_string())?)
+}
+
+fn history_order(pinned_first: Option<bool>) -> &'static str {
+    if pinned_first.unwrap_or(false) {
+        "pinned DESC, pin_order ASC, timestamp DESC"
+    } else {
+        "timestamp DESC"
+    }
+}
+
+/// Turns user input into an FTS5 query. Bare words are quoted so stray
+/// punctuation can't produce syntax errors, `"..."` is kept as a phrase,
+/// `AND`/`OR`/`NOT` pass through as operators and a trailing `*` marks a
+/// prefix. The last bare word is always treated as a prefix so results
+/// update while typing.
+fn build_fts_query(input: &str) -> Option<String> {
+    enum Token {
+        Term(String, bool),
+        Phrase(String),
+        Operator(&'static str),
+    }
+
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let phrase: String = chars.by_ref().take_while(|&c| c != '"').collect();
+            if !phrase.trim().is_empty() {
+                tokens.push(Token::Phrase(phrase.trim().to_string()));
+            }
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '"' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+
+            match word.as_str() {
+                "AND" => tokens.push(Token::Operator("AND")),
+                "OR" => tokens.push(Token::Operator("OR")),
+                "NOT" => tokens.push(Token::Operator("NOT")),
+                _ => {
+                    let prefix = word.ends_with('*');
+                    let term = word.trim_end_matches('*');
+                    if term.chars().any(char::is_alphanumeric) {
+                        tokens.push(Token::Term(term.to_string(), prefix));
+                    }
+                }
+            }
+        }
+    }
+
+    if
+        let Some(Token::Term(_, prefix)) = tokens
+            .iter_mut()
+            .rev()
+            .find(|t| !matches!(t, Token::Operator(_)))
+    {
+        *prefix = true;
+    }
+
+    let mut parts: Vec<String> = Vec::new();
+    for token in tokens {
+        match token {
+            Token::Operator(op) => {
+                // FTS5's NOT is binary, `a AND NOT b` is written `a NOT b`
+                if op == "NOT" && parts.last().map(String::as_str) == Some("AND") {
+                    parts.pop();
+                }
+                // Operators need an operand on both sides, drop dangling ones
+                if matches!(parts.last().map(String::as_str), None | Some("AND" | "OR" | "NOT")) {
+                    continue;
+                }
+                parts.push(op.to_string());
+            }
+            Token::Term(term, prefix) => {
+                let quoted = format!("\"{}\"", term);
+                parts.push(if prefix { format!("{}*", quoted) } else { quoted });
+            }
+            Token::Phrase(phrase) => {
+                parts.push(format!("\"{}\"", phrase));
+            }
+        }
+    }
+
+    while matches!(parts.last().map(String::as_str), Some("AND" | "OR" | "NOT")) {
+        parts.pop();
+    }
+
+    if parts.is_empty() { None } else { Some(parts.join(" ")) }
+}
+
+/// Deletes stored images and their thumbnails, ignoring anything outside
+/// `app_data_dir/images` so a tampered `content` column can't be used to
+/// remove arbitrary files.
+pub fn remove_image_files<I>(app_handle: &tauri::AppHandle, paths: I)
+    where I: IntoIterator<Item = String>
+{
+    let Ok(images_dir) = images_dir(app_handle) else {
+        return;
+    };
+
+    let paths = paths
+        .into_iter()
+        .flat_map(|path| {
+            let thumbnail = thumbnail::thumbnail_path(&path);
+            [PathBuf::from(path), thumbnail]
+        });
+
+    for path in paths {
+        match resolve_image_path(&images_dir, &path) {
+            Ok(path) => {
+                if let Err(e) = fs::remove_file(&path) {
+                    eprintln!("Failed to remove image {}: {}", path.display(), e);
+                }
+            }
+            Err(ImageError::AccessDenied) => {
+                eprintln!("Refusing to remove {} outside images dir", path.display());
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fts(input: &str) -> Option<String> {
+        build_fts_query(input)
+    }
+
+    #[test]
+    fn fts_query_quotes_terms_and_prefixes_the_last() {
+        assert_eq!(fts("foo bar"), Some("\"foo\" \"bar\"*".to_string()));
+        assert_eq!(fts("foo*"), Some("\"foo\"*".to_string()));
+        assert_eq!(fts("foo* bar"), Some("\"foo\"* \"bar\"*".to_string()));
+    }
+
+    #[test]
+    fn fts_query_handles_stray_quotes() {
+        assert_eq!(fts("foo \"bar"), Some("\"foo\" \"bar\"".to_string()));
+        assert_eq!(fts("\""), None);
+        assert_eq!(fts("\"\" foo"), Some("\"foo\"*".to_string()));
+    }
+
+    #[test]
+    fn fts_query_drops_dangling_operators() {
+        assert_eq!(fts("NOT foo"), Some("\"foo\"*".to_string()));
+        assert_eq!(fts("OR foo"), Some("\"foo\"*".to_string()));
+        assert_eq!(fts("foo OR"), Some("\"foo\"*".to_string()));
+        assert_eq!(fts("foo NOT"), Some("\"foo\"*".to_string()));
+        assert_eq!(fts("foo OR AND bar"), Some("\"foo\" OR \"bar\"*".to_string()));
+        assert_eq!(fts("OR"), None);
+        assert_eq!(fts("AND NOT"), None);
+    }
+
+    #[test]
+    fn fts_query_writes_and_not_as_not() {
+        assert_eq!(fts("foo AND NOT bar"), Some("\"foo\" NOT \"bar\"*".to_string()));
+    }
+
+    #[test]
+    fn fts_query_ignores_punctuation_only_input() {
+        assert_eq!(fts("?!."), None);
+        assert_eq!(fts("-- ** ::"), None);
+        assert_eq!(fts("foo ... bar"), Some("\"foo\" \"bar\"*".to_string()));
+    }
+
+    fn title_filter(title: &str) -> SearchFilter {
+        SearchFilter {
+            language: None,
+            kind: None,
+            window_title: Some(title.to_string()),
+            url: None,
+        }
+    }
+
+    #[test]
+    fn filters_apply_to_every_alternative_of_the_query() {
+        assert_eq!(
+            title_filter("Slack").match_query("a OR b"),
+            "(\"a\" OR \"b\"*) AND window_title : (\"Slack\"*)"
+        );
+        assert_eq!(title_filter("Slack").match_query(""), "window_title : (\"Slack\"*)");
+    }
+
+    #[test]
+    fn fts_query_keeps_operators_inside_phrases_literal() {
+        assert_eq!(
+            fts("\"rust OR go\" AND tokio"),
+            Some("\"rust OR go\" AND \"tokio\"*".to_string())
+        );
+        assert_eq!(fts("\"NOT this\""), Some("\"NOT this\"".to_string()));
+    }
+
+    #[test]
+    fn masked_info_hides_the_content() {
+        let info = ItemInfo::Link(InfoLink {
+            source: "Browser".to_string(),
+            title: Some("Reset your password".to_string()),
+            url: "https://example.com/reset?token=abc123".to_string(),
+            characters: 38,
+            copied: chrono::Utc::now(),
+        });
+        let ItemInfo::Link(info) = mask_info(info) else {
+            panic!("masking changed the content type");
+        };
+        assert_eq!(info.url, SENSITIVE_MASK);
+        assert_eq!(info.title, None);
+        assert_eq!(info.characters, 38);
+    }
+
+    /// A fresh `<tmp>/<name>/images` with one stored image and a file next to it.
+    fn fixture(name: &str) -> (PathBuf, PathBuf) {
+        let root = std::env::temp_dir().join(format!("qopy-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let images_dir = root.join("images");
+        fs::create_dir_all(&images_dir).unwrap();
+        fs::write(images_dir.join("stored.png"), b"png").unwrap();
+        fs::write(root.join("secret.txt"), b"secret").unwrap();
+        (root, images_dir)
+    }
+
+    #[test]
+    fn resolves_stored_images() {
+        let (root, images_dir) = fixture("stored");
+        let resolved = resolve_image_path(&images_dir, &images_dir.join("stored.png")).unwrap();
+        assert_eq!(resolved, images_dir.join("stored.png").canonicalize().unwrap());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn missing_image_is_not_access_denied() {
+        let (root, images_dir) = fixture("missing");
+        assert_eq!(
+            resolve_image_path(&images_dir, &images_dir.join("gone.png")),
+            Err(ImageError::MissingFile)
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn rejects_parent_traversal() {
+        let (root, images_dir) = fixture("traversal");
+        for path in [
+            images_dir.join("../secret.txt"),
+            images_dir.join("./../secret.txt"),
+            images_dir.join("../images/../secret.txt"),
+        ] {
+            assert_eq!(resolve_image_path(&images_dir, &path), Err(ImageError::AccessDenied));
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn traversal_to_missing_files_does_not_leak_existence() {
+        let (root, images_dir) = fixture("oracle");
+        for path in [images_dir.join("../nothing.txt"), images_dir.join("../../../../nothing")] {
+            assert_eq!(resolve_image_path(&images_dir, &path), Err(ImageError::AccessDenied));
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn rejects_paths_outside_and_relative_paths() {
+        let (root, images_dir) = fixture("outside");
+        for path in [root.join("secret.txt"), PathBuf::from("stored.png"), images_dir.clone()] {
+            assert_eq!(resolve_image_path(&images_dir, &path), Err(ImageError::AccessDenied));
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlinks_out_of_images_dir() {
+        let (root, images_dir) = fixture("symlink");
+        let link = images_dir.join("link.png");
+        std::os::unix::fs::symlink(root.join("secret.txt"), &link).unwrap();
+        assert_eq!(resolve_image_path(&images_dir, &link), Err(ImageError::AccessDenied));
+        fs::remove_dir_all(root).unwrap();
+    }
+}
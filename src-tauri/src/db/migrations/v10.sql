@@ -0,0 +1,2 @@
+ALTER TABLE history ADD COLUMN width INTEGER;
+ALTER TABLE history ADD COLUMN height INTEGER;
@@ -0,0 +1,26 @@
+CREATE TABLE IF NOT EXISTS sources (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    path TEXT,
+    icon TEXT,
+    first_seen DATETIME NOT NULL,
+    last_seen DATETIME NOT NULL
+);
+
+INSERT OR IGNORE INTO sources (name, icon, first_seen, last_seen)
+SELECT source,
+       (SELECT h.source_icon FROM history h
+        WHERE h.source = history.source AND h.source_icon IS NOT NULL
+        ORDER BY h.timestamp DESC LIMIT 1),
+       MIN(timestamp),
+       MAX(timestamp)
+FROM history
+GROUP BY source;
+
+ALTER TABLE history ADD COLUMN source_id INTEGER REFERENCES sources (id) ON DELETE SET NULL;
+
+UPDATE history
+SET source_id = (SELECT id FROM sources WHERE sources.name = history.source),
+    source_icon = NULL;
+
+CREATE INDEX IF NOT EXISTS idx_history_source_id ON history (source_id);
@@ -0,0 +1,62 @@
+ALTER TABLE history ADD COLUMN window_title TEXT;
+ALTER TABLE history ADD COLUMN source_url TEXT;
+
+DROP TRIGGER IF EXISTS history_fts_insert;
+DROP TRIGGER IF EXISTS history_fts_delete;
+DROP TRIGGER IF EXISTS history_fts_update;
+DROP TABLE IF EXISTS history_fts;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS history_fts USING fts5(
+    content,
+    window_title,
+    source_url,
+    content = 'history',
+    content_rowid = 'rowid',
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO history_fts (rowid, content, window_title, source_url)
+SELECT rowid, CASE WHEN content_type = 'image' THEN NULL ELSE content END, window_title, source_url
+FROM history;
+
+CREATE TRIGGER IF NOT EXISTS history_fts_insert AFTER INSERT ON history
+BEGIN
+    INSERT INTO history_fts (rowid, content, window_title, source_url)
+    VALUES (
+        new.rowid,
+        CASE WHEN new.content_type = 'image' THEN NULL ELSE new.content END,
+        new.window_title,
+        new.source_url
+    );
+END;
+
+CREATE TRIGGER IF NOT EXISTS history_fts_delete AFTER DELETE ON history
+BEGIN
+    INSERT INTO history_fts (history_fts, rowid, content, window_title, source_url)
+    VALUES (
+        'delete',
+        old.rowid,
+        CASE WHEN old.content_type = 'image' THEN NULL ELSE old.content END,
+        old.window_title,
+        old.source_url
+    );
+END;
+
+CREATE TRIGGER IF NOT EXISTS history_fts_update AFTER UPDATE OF content, content_type, window_title, source_url ON history
+BEGIN
+    INSERT INTO history_fts (history_fts, rowid, content, window_title, source_url)
+    VALUES (
+        'delete',
+        old.rowid,
+        CASE WHEN old.content_type = 'image' THEN NULL ELSE old.content END,
+        old.window_title,
+        old.source_url
+    );
+    INSERT INTO history_fts (rowid, content, window_title, source_url)
+    VALUES (
+        new.rowid,
+        CASE WHEN new.content_type = 'image' THEN NULL ELSE new.content END,
+        new.window_title,
+        new.source_url
+    );
+END;
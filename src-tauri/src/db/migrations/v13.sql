@@ -0,0 +1 @@
+ALTER TABLE history ADD COLUMN selection TEXT NOT NULL DEFAULT 'clipboard';
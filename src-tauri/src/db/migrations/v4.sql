@@ -0,0 +1,29 @@
+CREATE VIRTUAL TABLE IF NOT EXISTS history_fts USING fts5(
+    content,
+    content = 'history',
+    content_rowid = 'rowid',
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO history_fts (rowid, content)
+SELECT rowid, content FROM history WHERE content_type != 'image';
+
+CREATE TRIGGER IF NOT EXISTS history_fts_insert AFTER INSERT ON history
+WHEN new.content_type != 'image'
+BEGIN
+    INSERT INTO history_fts (rowid, content) VALUES (new.rowid, new.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS history_fts_delete AFTER DELETE ON history
+WHEN old.content_type != 'image'
+BEGIN
+    INSERT INTO history_fts (history_fts, rowid, content) VALUES ('delete', old.rowid, old.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS history_fts_update AFTER UPDATE OF content, content_type ON history
+BEGIN
+    INSERT INTO history_fts (history_fts, rowid, content)
+    SELECT 'delete', old.rowid, old.content WHERE old.content_type != 'image';
+    INSERT INTO history_fts (rowid, content)
+    SELECT new.rowid, new.content WHERE new.content_type != 'image';
+END;
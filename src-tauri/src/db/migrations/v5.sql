@@ -0,0 +1,3 @@
+ALTER TABLE history ADD COLUMN pinned BOOLEAN DEFAULT 0 NOT NULL;
+ALTER TABLE history ADD COLUMN pin_order INTEGER;
+CREATE INDEX IF NOT EXISTS idx_history_pinned ON history (pinned, pin_order);
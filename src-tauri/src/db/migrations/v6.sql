@@ -0,0 +1,2 @@
+ALTER TABLE history ADD COLUMN content_hash TEXT;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_history_content_hash ON history (content_hash);
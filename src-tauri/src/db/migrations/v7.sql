@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS history_representations (
+    history_id TEXT NOT NULL REFERENCES history (id) ON DELETE CASCADE,
+    format TEXT NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (history_id, format)
+);
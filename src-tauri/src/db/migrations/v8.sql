@@ -0,0 +1,3 @@
+ALTER TABLE history ADD COLUMN sensitive BOOLEAN DEFAULT 0 NOT NULL;
+ALTER TABLE history ADD COLUMN expires_at DATETIME;
+CREATE INDEX IF NOT EXISTS idx_history_expires_at ON history (expires_at) WHERE expires_at IS NOT NULL;
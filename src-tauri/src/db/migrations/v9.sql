@@ -0,0 +1,2 @@
+ALTER TABLE history ADD COLUMN kind TEXT;
+CREATE INDEX IF NOT EXISTS idx_history_kind ON history (kind) WHERE kind IS NOT NULL;
@@ -1,3 +1,15 @@
+pub mod archive;
 pub mod database;
+pub mod encryption;
 pub mod history;
+pub mod retention;
 pub mod settings;
+pub mod sources;
+
+use lazy_static::lazy_static;
+
+lazy_static! {
+    /// Held by everything that adds, bumps or deletes history rows, so a
+    /// re-key never misses a row written under the old key.
+    pub static ref WRITE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
+}
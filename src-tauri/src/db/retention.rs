@@ -0,0 +1,166 @@
+use crate::db::encryption;
+use crate::db::history::{ purge_expired, remove_image_files, sweep_images };
+use crate::db::settings::get_retention_policy;
+use crate::db::WRITE_LOCK;
+use sqlx::{ sqlite::SqliteRow, Row, SqlitePool };
+use std::collections::HashMap;
+use std::fs;
+use std::time::Duration;
+use tauri::{ AppHandle, Emitter, Listener, Manager };
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Sensitive items live for seconds, so expiry is checked far more often
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(5);
+
+pub fn setup(app_handle: AppHandle) {
+    let handle = app_handle.clone();
+    app_handle.listen("update-retention", move |_event| {
+        let handle = handle.clone();
+        tauri::async_runtime::spawn(async move {
+            run(&handle).await;
+        });
+    });
+
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(EXPIRY_INTERVAL);
+        loop {
+            interval.tick().await;
+            let pool = handle.state::<SqlitePool>();
+            let _write = WRITE_LOCK.lock().await;
+            match purge_expired(&handle, &pool).await {
+                Ok(0) => {}
+                Ok(_) => {
+                    let _ = handle.emit("clipboard-content-updated", ());
+                }
+                Err(e) => eprintln!("Failed to purge expired items: {}", e),
+            }
+        }
+    });
+
+    tauri::async_runtime::spawn(async move {
+        // The first tick fires immediately, which covers the startup pass
+        let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            interval.tick().await;
+            run(&app_handle).await;
+        }
+    });
+}
+
+async fn run(app_handle: &AppHandle) {
+    let _write = WRITE_LOCK.lock().await;
+    match prune_history(app_handle).await {
+        Ok(0) => {}
+        Ok(pruned) => {
+            println!("Retention pruned {} history items", pruned);
+            let _ = app_handle.emit("clipboard-content-updated", ());
+        }
+        Err(e) => eprintln!("Failed to apply retention policy: {}", e),
+    }
+
+    let pool = app_handle.state::<SqlitePool>();
+    match sweep_images(app_handle, &pool, false).await {
+        Ok(report) if !report.files.is_empty() => {
+            println!("Removed {} orphaned images ({} bytes)", report.files.len(), report.bytes);
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Failed to sweep orphaned images: {}", e),
+    }
+}
+
+/// Deletes every unpinned row that violates the stored retention policy,
+/// together with its image file, and returns how many rows were removed.
+pub async fn prune_history(app_handle: &AppHandle) -> Result<usize, sqlx::Error> {
+    // Image paths can't be resolved while the history is locked
+    if encryption::is_enabled() && !encryption::is_unlocked() {
+        return Ok(0);
+    }
+
+    let pool = app_handle.state::<SqlitePool>();
+    let policy = get_retention_policy(&pool).await;
+
+    // id -> content_type, content
+    let mut expired: HashMap<String, (String, String)> = HashMap::new();
+
+    if let Some(days) = policy.max_age_days {
+        let rows = sqlx
+            ::query(
+                "SELECT id, content_type, content FROM history
+                 WHERE pinned = 0 AND julianday(timestamp) < julianday('now', ?)"
+            )
+            .bind(format!("-{} days", days))
+            .fetch_all(&*pool).await?;
+
+        for row in rows {
+            expired.insert(row.get("id"), (row.get("content_type"), plain_content(&row)));
+        }
+    }
+
+    if let Some(max_items) = policy.max_items {
+        let rows = sqlx
+            ::query(
+                "SELECT id, content_type, content FROM history
+                 WHERE pinned = 0 ORDER BY timestamp DESC LIMIT -1 OFFSET ?"
+            )
+            .bind(max_items.max(0))
+            .fetch_all(&*pool).await?;
+
+        for row in rows {
+            expired.insert(row.get("id"), (row.get("content_type"), plain_content(&row)));
+        }
+    }
+
+    if let Some(max_image_bytes) = policy.max_image_bytes {
+        // Pinned images count towards the budget first but are never removed
+        let rows = sqlx
+            ::query(
+                "SELECT id, content, pinned FROM history
+                 WHERE content_type = 'image' ORDER BY pinned DESC, timestamp DESC"
+            )
+            .fetch_all(&*pool).await?;
+
+        let mut total: u64 = 0;
+        for row in rows {
+            let id: String = row.get("id");
+            let content = plain_content(&row);
+            let pinned: bool = row.get("pinned");
+
+            if expired.contains_key(&id) {
+                continue;
+            }
+
+            let size = fs::metadata(&content).map(|m| m.len()).unwrap_or(0);
+            if pinned || total + size <= max_image_bytes {
+                total += size;
+            } else {
+                expired.insert(id, ("image".to_string(), content));
+            }
+        }
+    }
+
+    if expired.is_empty() {
+        return Ok(0);
+    }
+
+    let mut tx = pool.begin().await?;
+    for id in expired.keys() {
+        sqlx::query("DELETE FROM history WHERE id = ? AND pinned = 0").bind(id).execute(&mut *tx).await?;
+    }
+    tx.commit().await?;
+
+    remove_image_files(
+        app_handle,
+        expired
+            .values()
+            .filter(|(content_type, _)| content_type == "image")
+            .map(|(_, content)| content.clone())
+    );
+
+    Ok(expired.len())
+}
+
+fn plain_content(row: &SqliteRow) -> String {
+    let content: String = row.get("content");
+    encryption::decrypt_field(&content).unwrap_or(content)
+}
@@ -1,5 +1,8 @@
-use serde::{ Deserialize, Serialize };
+use crate::utils::limits::LimitAction;
+use crate::utils::secrets::{ CustomSecretRule, SecretAction, HIGH_ENTROPY_RULE };
+use serde::{ de::DeserializeOwned, Deserialize, Serialize };
 use serde_json;
+use std::collections::HashMap;
 use sqlx::Row;
 use sqlx::SqlitePool;
 use tauri::{ Emitter, Manager };
@@ -10,6 +13,215 @@ struct KeybindSetting {
     keybind: Vec<String>,
 }
 
+/// Limits enforced by `db::retention`. `None` leaves that dimension unbounded,
+/// pinned items are never pruned.
+#[derive(Deserialize, Serialize, Default, Clone, Debug)]
+pub struct RetentionPolicy {
+    pub max_items: Option<i64>,
+    pub max_age_days: Option<i64>,
+    pub max_image_bytes: Option<u64>,
+}
+
+impl RetentionPolicy {
+    /// A maximum age of zero would prune the whole unpinned history, and a
+    /// negative one doesn't make a valid date modifier.
+    pub fn validate(&self) -> Result<(), String> {
+        match self.max_age_days {
+            Some(days) if days <= 0 => Err(format!("The maximum age has to be at least one day, not {}", days)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    #[default]
+    Passphrase,
+    KeyFile,
+}
+
+/// Stored under the `encryption` key and managed by `db::encryption`, not
+/// through `save_setting`.
+#[derive(Deserialize, Serialize, Default, Clone, Debug)]
+pub struct EncryptionConfig {
+    pub enabled: bool,
+    pub key_source: KeySource,
+    /// Base64 salt the passphrase key is derived with
+    pub salt: Option<String>,
+    /// Known plaintext sealed with the key, used to check unlock attempts
+    pub verifier: Option<String>,
+}
+
+/// Which source apps the clipboard monitor records, matched case-insensitively
+/// against the name `get_app_info` reports.
+#[derive(Deserialize, Serialize, Default, Clone, Debug)]
+pub struct AppFilter {
+    /// Only record copies from `allowlist` instead of everything but `blocklist`
+    pub allowlist_mode: bool,
+    pub blocklist: Vec<String>,
+    pub allowlist: Vec<String>,
+}
+
+impl AppFilter {
+    pub fn allows(&self, app_name: &str) -> bool {
+        let matches = |list: &[String]| list.iter().any(|app| app.eq_ignore_ascii_case(app_name));
+        if self.allowlist_mode {
+            matches(&self.allowlist)
+        } else {
+            !matches(&self.blocklist)
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SensitiveAction {
+    /// Don't record the copy at all
+    #[default]
+    Drop,
+    /// Record it masked and purge it after `ttl_seconds`
+    Expire,
+}
+
+/// How copies that password managers mark as concealed are handled.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct SensitivePolicy {
+    pub action: SensitiveAction,
+    pub ttl_seconds: u64,
+}
+
+impl Default for SensitivePolicy {
+    fn default() -> Self {
+        Self {
+            action: SensitiveAction::Drop,
+            ttl_seconds: 60,
+        }
+    }
+}
+
+/// Configuration of the secret scanner in `utils::secrets`, stored under the
+/// `secret_detection` key.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct SecretDetection {
+    pub enabled: bool,
+    /// Action for rules without an entry in `actions`
+    pub default_action: SecretAction,
+    /// Per rule overrides, keyed by built-in or custom rule name
+    pub actions: HashMap<String, SecretAction>,
+    pub disabled_rules: Vec<String>,
+    pub custom_rules: Vec<CustomSecretRule>,
+    /// Lifetime of items recorded with the `expire` action
+    pub ttl_seconds: u64,
+}
+
+impl Default for SecretDetection {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            default_action: SecretAction::Redact,
+            actions: HashMap::new(),
+            // Too noisy to redact by default, IDs and hashes look random too
+            disabled_rules: vec![HIGH_ENTROPY_RULE.to_string()],
+            custom_rules: Vec::new(),
+            ttl_seconds: 60,
+        }
+    }
+}
+
+impl SecretDetection {
+    /// The action for a rule, or `None` when the rule is disabled.
+    pub fn action_for(&self, rule: &str) -> Option<SecretAction> {
+        if self.disabled_rules.iter().any(|r| r == rule) {
+            return None;
+        }
+        let custom = self.custom_rules
+            .iter()
+            .find(|r| r.name == rule)
+            .and_then(|r| r.action);
+        Some(self.actions.get(rule).copied().or(custom).unwrap_or(self.default_action))
+    }
+}
+
+/// Stored under the `capture_pause` key. The pause itself is managed by
+/// `api::capture`.
+#[derive(Deserialize, Serialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct CapturePauseSettings {
+    /// Global shortcut toggling the pause, unset when empty
+    pub keybind: Vec<String>,
+    /// Duration the tray item and shortcut pause for, `None` pauses until resumed
+    pub default_minutes: Option<u64>,
+    /// Keep an active pause across restarts
+    pub persist: bool,
+}
+
+/// Stored under the `capture_limits` key and applied by `utils::limits`.
+/// `None` leaves that dimension unbounded.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct CaptureLimits {
+    pub max_text_bytes: Option<u64>,
+    pub max_image_pixels: Option<u64>,
+    /// Encoded size of the image as read from the clipboard
+    pub max_image_bytes: Option<u64>,
+    /// Files copied at once
+    pub max_files: Option<u64>,
+    pub text_action: LimitAction,
+    pub image_action: LimitAction,
+    pub file_action: LimitAction,
+}
+
+impl Default for CaptureLimits {
+    fn default() -> Self {
+        Self {
+            // Opt-in, nothing is limited until a maximum is set
+            max_text_bytes: None,
+            max_image_pixels: None,
+            max_image_bytes: None,
+            max_files: None,
+            text_action: LimitAction::Truncate,
+            image_action: LimitAction::Truncate,
+            file_action: LimitAction::Truncate,
+        }
+    }
+}
+
+/// Stored under the `capture_debounce` key. Clipboard updates arriving
+/// within `window_ms` of each other are captured once, as the last of them.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct CaptureDebounce {
+    /// Zero captures every update
+    pub window_ms: u64,
+}
+
+impl Default for CaptureDebounce {
+    fn default() -> Self {
+        Self { window_ms: 150 }
+    }
+}
+
+/// Stored under the `primary_selection` key, read by `api::selection`.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct PrimarySelectionSettings {
+    /// Also capture the PRIMARY selection, only available on Linux
+    pub enabled: bool,
+    /// How long a selection has to stay unchanged before it's captured
+    pub debounce_ms: u64,
+}
+
+impl Default for PrimarySelectionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            debounce_ms: 750,
+        }
+    }
+}
+
 pub async fn initialize_settings(pool: &SqlitePool) -> Result<(), Box<dyn std::error::Error>> {
     let default_keybind = KeybindSetting {
         keybind: vec!["Meta".to_string(), "V".to_string()],
@@ -38,6 +250,10 @@ pub async fn get_setting(
     Ok(row.map(|r| r.get("value")).unwrap_or_default())
 }
 
+/// Written by the backend only. The encryption salt and key source would
+/// make the history unreadable if replaced.
+pub const BACKEND_SETTINGS: &[&str] = &["encryption", "capture_pause_state"];
+
 #[tauri::command]
 pub async fn save_setting(
     app_handle: tauri::AppHandle,
@@ -45,6 +261,13 @@ pub async fn save_setting(
     key: String,
     value: String
 ) -> Result<(), String> {
+    if BACKEND_SETTINGS.contains(&key.as_str()) {
+        return Err(format!("The {} setting can't be changed directly", key));
+    }
+    if key == "retention" {
+        serde_json::from_str::<RetentionPolicy>(&value).map_err(|e| e.to_string())?.validate()?;
+    }
+
     sqlx
         ::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
         .bind(key.clone())
@@ -63,6 +286,22 @@ pub async fn save_setting(
         let _ = app_handle.emit("update-shortcut", &value).map_err(|e| e.to_string())?;
     }
 
+    if key == "capture_pause" {
+        let _ = app_handle.emit("update-pause-shortcut", &value).map_err(|e| e.to_string())?;
+    }
+
+    if key == "retention" {
+        let _ = app_handle.emit("update-retention", &value).map_err(|e| e.to_string())?;
+    }
+
+    if key == "capture_debounce" {
+        let _ = app_handle.emit("update-capture-debounce", &value).map_err(|e| e.to_string())?;
+    }
+
+    if key == "primary_selection" {
+        let _ = app_handle.emit("update-primary-selection", &value).map_err(|e| e.to_string())?;
+    }
+
     Ok(())
 }
 
@@ -85,3 +324,128 @@ pub async fn get_keybind(app_handle: tauri::AppHandle) -> Result<Vec<String>, St
 
     serde_json::from_str::<Vec<String>>(&json).map_err(|e| e.to_string())
 }
+
+/// Reads a JSON encoded setting, falling back to the default when the key is
+/// missing or holds something that doesn't parse.
+pub async fn get_json_setting<T: DeserializeOwned + Default>(pool: &SqlitePool, key: &str) -> T {
+    let row = sqlx
+        ::query("SELECT value FROM settings WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool).await
+        .ok()
+        .flatten();
+
+    row.and_then(|r| serde_json::from_str(&r.get::<String, _>("value")).ok()).unwrap_or_default()
+}
+
+/// The stored policy, without a maximum age that fails `validate`. Imported
+/// archives don't go through `save_setting`.
+pub async fn get_retention_policy(pool: &SqlitePool) -> RetentionPolicy {
+    let policy: RetentionPolicy = get_json_setting(pool, "retention").await;
+    match policy.validate() {
+        Ok(()) => policy,
+        Err(e) => {
+            eprintln!("Ignoring stored retention age: {}", e);
+            RetentionPolicy { max_age_days: None, ..policy }
+        }
+    }
+}
+
+pub async fn get_encryption_config(pool: &SqlitePool) -> EncryptionConfig {
+    get_json_setting(pool, "encryption").await
+}
+
+pub async fn get_app_filter_setting(pool: &SqlitePool) -> AppFilter {
+    get_json_setting(pool, "app_filter").await
+}
+
+async fn save_app_filter_setting(pool: &SqlitePool, filter: &AppFilter) -> Result<(), String> {
+    let json = serde_json::to_string(filter).map_err(|e| e.to_string())?;
+    sqlx
+        ::query("INSERT OR REPLACE INTO settings (key, value) VALUES ('app_filter', ?)")
+        .bind(json)
+        .execute(pool).await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_app_filter(pool: tauri::State<'_, SqlitePool>) -> Result<AppFilter, String> {
+    Ok(get_app_filter_setting(&pool).await)
+}
+
+#[tauri::command]
+pub async fn save_app_filter(
+    app_handle: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    filter: AppFilter
+) -> Result<(), String> {
+    save_app_filter_setting(&pool, &filter).await?;
+
+    let _ = app_handle.track_event(
+        "app_filter_saved",
+        Some(serde_json::json!({
+        "allowlist_mode": filter.allowlist_mode
+    }))
+    );
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn exclude_app(pool: tauri::State<'_, SqlitePool>, app_name: String) -> Result<(), String> {
+    let mut filter = get_app_filter_setting(&pool).await;
+    if !filter.blocklist.iter().any(|app| app.eq_ignore_ascii_case(&app_name)) {
+        filter.blocklist.push(app_name);
+        save_app_filter_setting(&pool, &filter).await?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unexclude_app(pool: tauri::State<'_, SqlitePool>, app_name: String) -> Result<(), String> {
+    let mut filter = get_app_filter_setting(&pool).await;
+    filter.blocklist.retain(|app| !app.eq_ignore_ascii_case(&app_name));
+    save_app_filter_setting(&pool, &filter).await
+}
+
+pub async fn get_sensitive_policy(pool: &SqlitePool) -> SensitivePolicy {
+    get_json_setting(pool, "sensitive_policy").await
+}
+
+pub async fn get_secret_detection(pool: &SqlitePool) -> SecretDetection {
+    get_json_setting(pool, "secret_detection").await
+}
+
+pub async fn get_capture_pause_settings(pool: &SqlitePool) -> CapturePauseSettings {
+    get_json_setting(pool, "capture_pause").await
+}
+
+pub async fn get_capture_limits(pool: &SqlitePool) -> CaptureLimits {
+    get_json_setting(pool, "capture_limits").await
+}
+
+pub async fn get_capture_debounce(pool: &SqlitePool) -> CaptureDebounce {
+    get_json_setting(pool, "capture_debounce").await
+}
+
+pub async fn get_primary_selection_settings(pool: &SqlitePool) -> PrimarySelectionSettings {
+    get_json_setting(pool, "primary_selection").await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn max_age(days: i64) -> RetentionPolicy {
+        RetentionPolicy { max_age_days: Some(days), ..RetentionPolicy::default() }
+    }
+
+    #[test]
+    fn retention_age_has_to_be_positive() {
+        assert!(max_age(30).validate().is_ok());
+        assert!(RetentionPolicy::default().validate().is_ok());
+        assert!(max_age(0).validate().is_err());
+        assert!(max_age(-7).validate().is_err());
+    }
+}
@@ -0,0 +1,73 @@
+use crate::db::encryption;
+use crate::utils::types::Source;
+use chrono::{ DateTime, Utc };
+use sqlx::{ Row, SqlitePool };
+
+/// Records a copy from the app `name` at `seen` and returns the app's id.
+/// The icon is only stored the first time the app is seen, it isn't even
+/// encrypted for an app that already has one.
+pub async fn upsert_source(
+    pool: &SqlitePool,
+    name: &str,
+    path: Option<&str>,
+    icon: Option<String>,
+    seen: DateTime<Utc>
+) -> Result<i64, String> {
+    let has_icon: bool = sqlx
+        ::query_scalar("SELECT icon IS NOT NULL FROM sources WHERE name = ?")
+        .bind(name)
+        .fetch_optional(pool).await
+        .map_err(|e| e.to_string())?
+        .unwrap_or(false);
+    let icon = if has_icon { None } else { encryption::encrypt_optional(icon)? };
+
+    sqlx
+        ::query_scalar(
+            "INSERT INTO sources (name, path, icon, first_seen, last_seen) VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT (name) DO UPDATE SET
+                 path = COALESCE(excluded.path, sources.path),
+                 icon = COALESCE(sources.icon, excluded.icon),
+                 first_seen = CASE WHEN julianday(excluded.first_seen) < julianday(sources.first_seen)
+                     THEN excluded.first_seen ELSE sources.first_seen END,
+                 last_seen = CASE WHEN julianday(excluded.last_seen) > julianday(sources.last_seen)
+                     THEN excluded.last_seen ELSE sources.last_seen END
+             RETURNING id"
+        )
+        .bind(name)
+        .bind(path)
+        .bind(icon)
+        .bind(seen)
+        .bind(seen)
+        .fetch_one(pool).await
+        .map_err(|e| e.to_string())
+}
+
+/// Apps items were copied from, most recently used first, with their icons
+/// and how many history items each one has.
+#[tauri::command]
+pub async fn get_sources(pool: tauri::State<'_, SqlitePool>) -> Result<Vec<Source>, String> {
+    let rows = sqlx
+        ::query(
+            "SELECT s.id, s.name, s.path, s.icon, s.first_seen, s.last_seen, COUNT(h.id) AS count
+             FROM sources s
+             LEFT JOIN history h ON h.source_id = s.id
+             GROUP BY s.id
+             ORDER BY s.last_seen DESC"
+        )
+        .fetch_all(&*pool).await
+        .map_err(|e| e.to_string())?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(Source {
+                id: row.get("id"),
+                name: row.get("name"),
+                path: row.get("path"),
+                icon: encryption::decrypt_optional(row.get("icon"))?,
+                first_seen: row.get("first_seen"),
+                last_seen: row.get("last_seen"),
+                count: row.get("count"),
+            })
+        })
+        .collect()
+}
@@ -24,6 +24,7 @@ fn main() {
     }
 
     tauri::Builder::default()
+        .register_asynchronous_uri_scheme_protocol(api::images::SCHEME, api::images::handle)
         .plugin(tauri_plugin_clipboard::init())
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_sql::Builder::default().build())
@@ -92,10 +93,14 @@ fn main() {
 
             let main_window = app.get_webview_window("main");
 
+            utils::apps::setup();
             let _ = db::database::setup(app);
+            db::retention::setup(app_handle.clone());
+            api::capture::setup(app_handle.clone());
             api::hotkeys::setup(app_handle.clone());
             api::tray::setup(app)?;
             api::clipboard::setup(app.handle());
+            api::selection::setup(app_handle.clone());
             let _ = api::clipboard::start_monitor(app_handle.clone());
 
             utils::commands::center_window_on_current_monitor(main_window.as_ref().unwrap());
@@ -119,16 +124,43 @@ fn main() {
         })
         .invoke_handler(tauri::generate_handler![
             api::clipboard::write_and_paste,
+            api::capture::get_capture_status,
+            api::capture::pause_capture,
+            api::capture::resume_capture,
+            api::capture::toggle_capture,
             db::history::get_history,
             db::history::add_history_item,
             db::history::search_history,
+            db::history::get_history_languages,
             db::history::load_history_chunk,
             db::history::delete_history_item,
             db::history::clear_history,
+            db::history::pin_history_item,
+            db::history::unpin_history_item,
+            db::history::reorder_pinned_items,
+            db::history::sweep_orphaned_images,
             db::history::read_image,
+            db::history::get_rich_content,
+            db::history::reveal_history_item,
+            db::history::get_item_info,
+            db::sources::get_sources,
+            db::archive::export_history,
+            db::archive::import_history,
+            db::encryption::get_encryption_status,
+            db::encryption::unlock_history,
+            db::encryption::enable_encryption,
+            db::encryption::rotate_encryption_key,
+            db::encryption::disable_encryption,
             db::settings::get_setting,
             db::settings::save_setting,
-            utils::commands::fetch_page_meta
+            db::settings::get_app_filter,
+            db::settings::save_app_filter,
+            db::settings::exclude_app,
+            db::settings::unexclude_app,
+            utils::commands::fetch_page_meta,
+            utils::commands::format_json,
+            utils::commands::convert_color,
+            utils::commands::analyze_color
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,106 @@
+use applications::{ utils::image::RustImage, App, AppInfo, AppInfoContext, AppTrait };
+use base64::{ engine::general_purpose::STANDARD, Engine };
+use lazy_static::lazy_static;
+use parking_lot::{ Mutex, RwLock };
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{ sync_channel, SyncSender };
+use std::time::{ Duration, Instant };
+
+/// How often the installed apps are rescanned in the background.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// Lookups for an unknown app rescan early, but at most this often.
+const MIN_REFRESH_GAP: Duration = Duration::from_secs(30);
+
+lazy_static! {
+    static ref REGISTRY: Mutex<AppInfoContext> = Mutex::new(AppInfoContext::new(vec![]));
+    static ref LAST_REFRESH: Mutex<Option<Instant>> = Mutex::new(None);
+    /// Base64 PNG icons by app path, `None` for apps without a loadable icon
+    static ref ICONS: RwLock<HashMap<String, Option<String>>> = RwLock::new(HashMap::new());
+    /// Wakes the background thread for an early rescan
+    static ref REFRESH_REQUESTS: Mutex<Option<SyncSender<()>>> = Mutex::new(None);
+}
+
+/// Scans the installed apps off the main thread and keeps rescanning on a
+/// schedule, or earlier when a lookup asks for it.
+pub fn setup() {
+    // Requests made while one is pending are covered by it
+    let (sender, requests) = sync_channel(1);
+    *REFRESH_REQUESTS.lock() = Some(sender);
+    std::thread::spawn(move || {
+        loop {
+            refresh();
+            let _ = requests.recv_timeout(REFRESH_INTERVAL);
+        }
+    });
+}
+
+/// Name, executable or bundle path and base64 icon of the frontmost application.
+pub fn get_app_info() -> (String, Option<String>, Option<String>) {
+    let frontmost = REGISTRY.lock().get_frontmost_application();
+    match frontmost {
+        Ok(app) => {
+            let path = app_path(&app);
+            let icon = cached_icon(&path, || {
+                app.load_icon()
+                    .ok()
+                    .and_then(|icon| icon.to_png().ok())
+                    .map(|png| STANDARD.encode(png.get_bytes()))
+            });
+            (app.name, Some(path), icon)
+        }
+        Err(e) => {
+            // The app may have been installed since the last scan, the next
+            // copy from it will find it
+            request_refresh();
+            println!("Failed to get frontmost application: {:?}", e);
+            ("System".to_string(), None, None)
+        }
+    }
+}
+
+fn app_path(app: &App) -> String {
+    app.app_path_exe.as_ref().unwrap_or(&app.app_desktop_path).to_string_lossy().into_owned()
+}
+
+fn icon_paths(ctx: &AppInfoContext) -> HashMap<String, Option<PathBuf>> {
+    ctx.get_all_apps()
+        .into_iter()
+        .map(|app| (app_path(&app), app.icon_path))
+        .collect()
+}
+
+/// Builds a fresh registry and swaps it in, so lookups aren't blocked during
+/// the scan. Icons of apps that were removed or changed their icon are
+/// dropped, and ones that failed to load are tried again.
+fn refresh() {
+    let mut ctx = AppInfoContext::new(vec![]);
+    if let Err(e) = ctx.refresh_apps() {
+        eprintln!("Failed to refresh apps: {:?}", e);
+        return;
+    }
+    let current = icon_paths(&ctx);
+    let previous = std::mem::replace(&mut *REGISTRY.lock(), ctx);
+    let previous = icon_paths(&previous);
+    ICONS.write().retain(|path, icon| icon.is_some() && current.get(path) == previous.get(path));
+    *LAST_REFRESH.lock() = Some(Instant::now());
+}
+
+/// Wakes the background thread unless it rescanned within `MIN_REFRESH_GAP`.
+fn request_refresh() {
+    let stale = !matches!(*LAST_REFRESH.lock(), Some(at) if at.elapsed() < MIN_REFRESH_GAP);
+    if stale {
+        if let Some(sender) = REFRESH_REQUESTS.lock().as_ref() {
+            let _ = sender.try_send(());
+        }
+    }
+}
+
+fn cached_icon(path: &str, load: impl FnOnce() -> Option<String>) -> Option<String> {
+    if let Some(icon) = ICONS.read().get(path) {
+        return icon.clone();
+    }
+    let icon = load();
+    ICONS.write().insert(path.to_string(), icon.clone());
+    icon
+}
@@ -0,0 +1,607 @@
+use serde::{ Deserialize, Serialize };
+use std::f64::consts::PI;
+
+/// A color in sRGB with every channel and alpha in `0.0..=1.0`. Parsed
+/// colors outside of sRGB (wide oklch values) are clamped.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Color {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub alpha: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorFormat {
+    Hex,
+    Rgb,
+    Hsl,
+    Hwb,
+    Oklch,
+    /// The CSS keyword, only available for exact matches
+    Name,
+}
+
+impl Color {
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim().to_lowercase();
+
+        if let Some(hex) = input.strip_prefix('#') {
+            return parse_hex(hex);
+        }
+
+        if let Some((name, rest)) = input.split_once('(') {
+            let args = rest.strip_suffix(')')?;
+            if args.contains(['(', ')']) {
+                return None;
+            }
+            let args: Vec<&str> = args
+                .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+                .filter(|arg| !arg.is_empty())
+                .collect();
+            if args.len() != 3 && args.len() != 4 {
+                return None;
+            }
+            let alpha = match args.get(3) {
+                Some(alpha) => parse_alpha(alpha)?,
+                None => 1.0,
+            };
+
+            return match name.trim() {
+                "rgb" | "rgba" =>
+                    Some(
+                        Color::new(
+                            parse_channel(args[0])?,
+                            parse_channel(args[1])?,
+                            parse_channel(args[2])?,
+                            alpha
+                        )
+                    ),
+                "hsl" | "hsla" =>
+                    Some(
+                        Color::from_hsl(
+                            parse_hue(args[0])?,
+                            parse_percentage(args[1])?,
+                            parse_percentage(args[2])?,
+                            alpha
+                        )
+                    ),
+                "hwb" =>
+                    Some(
+                        Color::from_hwb(
+                            parse_hue(args[0])?,
+                            parse_percentage(args[1])?,
+                            parse_percentage(args[2])?,
+                            alpha
+                        )
+                    ),
+                "oklch" => {
+                    let lightness = match args[0].strip_suffix('%') {
+                        Some(percent) => percent.parse::<f64>().ok()? / 100.0,
+                        None => parse_number(args[0])?,
+                    };
+                    let chroma = match args[1].strip_suffix('%') {
+                        Some(percent) => (percent.parse::<f64>().ok()? / 100.0) * 0.4,
+                        None => parse_number(args[1])?,
+                    };
+                    Some(Color::from_oklch(lightness, chroma, parse_hue(args[2])?, alpha))
+                }
+                _ => None,
+            };
+        }
+
+        if input == "transparent" {
+            return Some(Color::new(0.0, 0.0, 0.0, 0.0));
+        }
+        NAMED_COLORS.iter()
+            .find(|(name, _)| *name == input)
+            .map(|(_, hex)| Color::from_rgb24(*hex, 1.0))
+    }
+
+    fn new(r: f64, g: f64, b: f64, alpha: f64) -> Self {
+        Self {
+            r: r.clamp(0.0, 1.0),
+            g: g.clamp(0.0, 1.0),
+            b: b.clamp(0.0, 1.0),
+            alpha: alpha.clamp(0.0, 1.0),
+        }
+    }
+
+    fn from_rgb24(rgb: u32, alpha: f64) -> Self {
+        Color::new(
+            (((rgb >> 16) & 0xff) as f64) / 255.0,
+            (((rgb >> 8) & 0xff) as f64) / 255.0,
+            ((rgb & 0xff) as f64) / 255.0,
+            alpha
+        )
+    }
+
+    fn from_hsl(hue: f64, saturation: f64, lightness: f64, alpha: f64) -> Self {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let channel = |n: f64| {
+            let k = (n + hue / 30.0) % 12.0;
+            lightness - (chroma / 2.0) * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+        };
+        Color::new(channel(0.0), channel(8.0), channel(4.0), alpha)
+    }
+
+    fn from_hwb(hue: f64, whiteness: f64, blackness: f64, alpha: f64) -> Self {
+        if whiteness + blackness >= 1.0 {
+            let gray = whiteness / (whiteness + blackness);
+            return Color::new(gray, gray, gray, alpha);
+        }
+        let pure = Color::from_hsl(hue, 1.0, 0.5, 1.0);
+        let mix = |c: f64| c * (1.0 - whiteness - blackness) + whiteness;
+        Color::new(mix(pure.r), mix(pure.g), mix(pure.b), alpha)
+    }
+
+    fn from_oklch(lightness: f64, chroma: f64, hue: f64, alpha: f64) -> Self {
+        let a = chroma * (hue * PI / 180.0).cos();
+        let b = chroma * (hue * PI / 180.0).sin();
+
+        let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+        let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+        let s = (lightness - 0.0894841775 * a - 1.291485548 * b).powi(3);
+
+        Color::new(
+            to_srgb(4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s),
+            to_srgb(-1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s),
+            to_srgb(-0.0041960863 * l - 0.7034186147 * m + 1.707614701 * s),
+            alpha
+        )
+    }
+
+    /// Hue in degrees, saturation and lightness in `0.0..=1.0`.
+    fn to_hsl(self) -> (f64, f64, f64) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let lightness = (max + min) / 2.0;
+        let delta = max - min;
+        if delta == 0.0 {
+            return (0.0, 0.0, lightness);
+        }
+        let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+        (self.hue(max, delta), saturation, lightness)
+    }
+
+    fn hue(self, max: f64, delta: f64) -> f64 {
+        let hue = if max == self.r {
+            ((self.g - self.b) / delta).rem_euclid(6.0)
+        } else if max == self.g {
+            (self.b - self.r) / delta + 2.0
+        } else {
+            (self.r - self.g) / delta + 4.0
+        };
+        hue * 60.0
+    }
+
+    fn to_oklch(self) -> (f64, f64, f64) {
+        let (r, g, b) = (to_linear(self.r), to_linear(self.g), to_linear(self.b));
+        let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+        let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+        let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+
+        let lightness = 0.2104542553 * l + 0.793617785 * m - 0.0040720468 * s;
+        let a = 1.9779984951 * l - 2.428592205 * m + 0.4505937099 * s;
+        let b = 0.0259040371 * l + 0.7827717662 * m - 0.808675766 * s;
+
+        let chroma = (a * a + b * b).sqrt();
+        // Grays have no meaningful hue
+        let hue = if chroma < 0.0001 { 0.0 } else { b.atan2(a).to_degrees().rem_euclid(360.0) };
+        (lightness, chroma, hue)
+    }
+
+    fn to_rgb24(self) -> u32 {
+        let channel = |c: f64| (c * 255.0).round() as u32;
+        (channel(self.r) << 16) | (channel(self.g) << 8) | channel(self.b)
+    }
+
+    pub fn format(self, format: ColorFormat) -> Option<String> {
+        let opaque = self.alpha >= 1.0;
+        let alpha = number(self.alpha, 3);
+
+        let formatted = match format {
+            ColorFormat::Hex if opaque => format!("#{:06x}", self.to_rgb24()),
+            ColorFormat::Hex =>
+                format!("#{:06x}{:02x}", self.to_rgb24(), (self.alpha * 255.0).round() as u8),
+            ColorFormat::Rgb => {
+                let rgb = self.to_rgb24();
+                let (r, g, b) = ((rgb >> 16) & 0xff, (rgb >> 8) & 0xff, rgb & 0xff);
+                if opaque {
+                    format!("rgb({}, {}, {})", r, g, b)
+                } else {
+                    format!("rgba({}, {}, {}, {})", r, g, b, alpha)
+                }
+            }
+            ColorFormat::Hsl => {
+                let (h, s, l) = self.to_hsl();
+                let (h, s, l) = (number(h, 1), number(s * 100.0, 1), number(l * 100.0, 1));
+                if opaque {
+                    format!("hsl({}, {}%, {}%)", h, s, l)
+                } else {
+                    format!("hsla({}, {}%, {}%, {})", h, s, l, alpha)
+                }
+            }
+            ColorFormat::Hwb => {
+                let max = self.r.max(self.g).max(self.b);
+                let min = self.r.min(self.g).min(self.b);
+                let hue = if max == min { 0.0 } else { self.hue(max, max - min) };
+                let hwb = format!(
+                    "{} {}% {}%",
+                    number(hue, 1),
+                    number(min * 100.0, 1),
+                    number((1.0 - max) * 100.0, 1)
+                );
+                with_alpha("hwb", hwb, opaque, &alpha)
+            }
+            ColorFormat::Oklch => {
+                let (l, c, h) = self.to_oklch();
+                let oklch = format!("{}% {} {}", number(l * 100.0, 2), number(c, 4), number(h, 2));
+                with_alpha("oklch", oklch, opaque, &alpha)
+            }
+            ColorFormat::Name => {
+                let rgb = self.to_rgb24();
+                return NAMED_COLORS.iter()
+                    .find(|(_, hex)| *hex == rgb && opaque)
+                    .map(|(name, _)| name.to_string());
+            }
+        };
+
+        Some(formatted)
+    }
+
+    /// WCAG relative luminance.
+    pub fn luminance(self) -> f64 {
+        0.2126 * to_linear(self.r) + 0.7152 * to_linear(self.g) + 0.0722 * to_linear(self.b)
+    }
+
+    /// WCAG contrast ratio between `1.0` and `21.0`, ignoring alpha.
+    pub fn contrast_ratio(self, other: Color) -> f64 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+}
+
+/// Whether `text` is a color literal worth capturing as a color item. Named
+/// colors are left out, otherwise copying the word "red" would become one.
+pub fn is_color_literal(text: &str) -> bool {
+    let text = text.trim();
+    match text.strip_prefix('#') {
+        Some(hex) => is_likely_hex_color(hex) && Color::parse(text).is_some(),
+        None => text.ends_with(')') && Color::parse(text).is_some(),
+    }
+}
+
+/// `#RRGGBB`, `#RGB` and `#RRGGBBAA`. The rarely used `#RGBA` form is left
+/// out, copies like `#1234` or `#cafe` are issue numbers and tags far more
+/// often. A `#123` inside a sentence never gets here, the whole copy has to
+/// be the literal.
+fn is_likely_hex_color(hex: &str) -> bool {
+    matches!(hex.len(), 3 | 6 | 8)
+}
+
+fn with_alpha(name: &str, components: String, opaque: bool, alpha: &str) -> String {
+    if opaque {
+        format!("{}({})", name, components)
+    } else {
+        format!("{}({} / {})", name, components, alpha)
+    }
+}
+
+/// `value` rounded to `decimals` places without trailing zeros.
+fn number(value: f64, decimals: usize) -> String {
+    let formatted = format!("{:.*}", decimals, value);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    if trimmed == "-0" { "0".to_string() } else { trimmed.to_string() }
+}
+
+fn to_linear(c: f64) -> f64 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+fn to_srgb(c: f64) -> f64 {
+    if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let expanded: String = match hex.len() {
+        3 | 4 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 | 8 => hex.to_string(),
+        _ => {
+            return None;
+        }
+    };
+    let value = u32::from_str_radix(&expanded[..6], 16).ok()?;
+    let alpha = match expanded.get(6..8) {
+        Some(alpha) => (u8::from_str_radix(alpha, 16).ok()? as f64) / 255.0,
+        None => 1.0,
+    };
+    Some(Color::from_rgb24(value, alpha))
+}
+
+/// `none` counts as zero, like in CSS Color 4.
+fn parse_number(value: &str) -> Option<f64> {
+    if value == "none" {
+        return Some(0.0);
+    }
+    value.parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+fn parse_channel(value: &str) -> Option<f64> {
+    match value.strip_suffix('%') {
+        Some(percent) => Some(parse_number(percent)? / 100.0),
+        None => Some(parse_number(value)? / 255.0),
+    }
+}
+
+fn parse_alpha(value: &str) -> Option<f64> {
+    match value.strip_suffix('%') {
+        Some(percent) => Some(parse_number(percent)? / 100.0),
+        None => parse_number(value),
+    }
+}
+
+/// Percentages, where bare numbers are read as percent too for lenient input.
+fn parse_percentage(value: &str) -> Option<f64> {
+    Some(parse_number(value.strip_suffix('%').unwrap_or(value))? / 100.0)
+}
+
+/// Hue in degrees from `deg`, `rad`, `grad`, `turn` or a bare number.
+fn parse_hue(value: &str) -> Option<f64> {
+    let degrees = if let Some(v) = value.strip_suffix("deg") {
+        parse_number(v)?
+    } else if let Some(v) = value.strip_suffix("grad") {
+        parse_number(v)? * 0.9
+    } else if let Some(v) = value.strip_suffix("rad") {
+        parse_number(v)?.to_degrees()
+    } else if let Some(v) = value.strip_suffix("turn") {
+        parse_number(v)? * 360.0
+    } else {
+        parse_number(value)?
+    };
+    Some(degrees.rem_euclid(360.0))
+}
+
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(input: &str) -> String {
+        Color::parse(input).unwrap().format(ColorFormat::Hex).unwrap()
+    }
+
+    #[test]
+    fn parses_every_syntax() {
+        assert_eq!(hex("#F00"), "#ff0000");
+        assert_eq!(hex("#ff000080"), "#ff000080");
+        assert_eq!(hex("rgb(255, 0, 0)"), "#ff0000");
+        assert_eq!(hex("rgb(100% 0% 0% / 50%)"), "#ff000080");
+        assert_eq!(hex("rgba(0, 128, 0, 0.5)"), "#00800080");
+        assert_eq!(hex("hsl(120, 100%, 25%)"), "#008000");
+        assert_eq!(hex("hsla(0.5turn 100% 50% / 1)"), "#00ffff");
+        assert_eq!(hex("hwb(240 0% 0%)"), "#0000ff");
+        assert_eq!(hex("hwb(0 50% 50%)"), "#808080");
+        assert_eq!(hex("oklch(62.8% 0.2577 29.23)"), "#ff0000");
+        assert_eq!(hex("RebeccaPurple"), "#663399");
+        assert_eq!(hex("transparent"), "#00000000");
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        for input in ["#ff", "#gggggg", "rgb(1, 2)", "rgb(1, 2, 3", "hsl(a, b, c)", "notacolor", "rgb((1, 2, 3))"] {
+            assert_eq!(Color::parse(input), None, "{}", input);
+        }
+    }
+
+    #[test]
+    fn formats_every_target() {
+        let color = Color::parse("#663399").unwrap();
+        assert_eq!(color.format(ColorFormat::Rgb).unwrap(), "rgb(102, 51, 153)");
+        assert_eq!(color.format(ColorFormat::Hsl).unwrap(), "hsl(270, 50%, 40%)");
+        assert_eq!(color.format(ColorFormat::Hwb).unwrap(), "hwb(270 20% 40%)");
+        assert_eq!(color.format(ColorFormat::Name).unwrap(), "rebeccapurple");
+        assert_eq!(Color::parse("#663398").unwrap().format(ColorFormat::Name), None);
+
+        let translucent = Color::parse("rgba(255, 0, 0, 0.5)").unwrap();
+        assert_eq!(translucent.format(ColorFormat::Rgb).unwrap(), "rgba(255, 0, 0, 0.5)");
+        assert_eq!(translucent.format(ColorFormat::Hwb).unwrap(), "hwb(0 0% 0% / 0.5)");
+    }
+
+    #[test]
+    fn round_trips_through_oklch() {
+        for input in ["#ff0000", "#00ff00", "#1e90ff", "#808080", "#ffffff", "#000000"] {
+            let oklch = Color::parse(input).unwrap().format(ColorFormat::Oklch).unwrap();
+            assert_eq!(hex(&oklch), input, "{}", oklch);
+        }
+    }
+
+    #[test]
+    fn computes_luminance_and_contrast() {
+        let white = Color::parse("white").unwrap();
+        let black = Color::parse("black").unwrap();
+        assert!((white.luminance() - 1.0).abs() < 1e-9);
+        assert!(black.luminance().abs() < 1e-9);
+        assert!((white.contrast_ratio(black) - 21.0).abs() < 1e-9);
+        let ratio = Color::parse("#777777").unwrap().contrast_ratio(white);
+        assert!((ratio - 4.48).abs() < 0.01, "{}", ratio);
+    }
+
+    #[test]
+    fn only_literals_are_captured() {
+        assert!(is_color_literal("#fff"));
+        assert!(is_color_literal("oklch(70% 0.1 200)"));
+        assert!(!is_color_literal("red"));
+        assert!(!is_color_literal("#notacolor"));
+    }
+
+    #[test]
+    fn every_short_long_and_alpha_hex_is_a_color() {
+        assert!(is_color_literal("#000"));
+        assert!(is_color_literal("#999"));
+        assert!(is_color_literal("#1e90ff"));
+        assert!(is_color_literal("#00000080"));
+        assert!(is_color_literal("#ffffffff"));
+    }
+
+    #[test]
+    fn four_digit_hex_and_sentences_are_not_colors() {
+        assert!(!is_color_literal("#1234"));
+        assert!(!is_color_literal("#cafe"));
+        assert!(!is_color_literal("see #123 for details"));
+    }
+}
@@ -1,8 +1,11 @@
-use applications::{AppInfoContext, AppInfo, AppTrait, utils::image::RustImage};
 use base64::{ engine::general_purpose::STANDARD, Engine };
 use image::codecs::png::PngEncoder;
 use tauri::PhysicalPosition;
 use meta_fetcher;
+use sqlx::SqlitePool;
+use crate::db::history;
+use crate::utils::color::{ Color, ColorFormat };
+use crate::utils::types::ColorAnalysis;
 
 pub fn center_window_on_current_monitor(window: &tauri::WebviewWindow) {
     if
@@ -36,36 +39,6 @@ pub fn center_window_on_current_monitor(window: &tauri::WebviewWindow) {
     }
 }
 
-pub fn get_app_info() -> (String, Option<String>) {
-    println!("Getting app info");
-    let mut ctx = AppInfoContext::new(vec![]);
-    println!("Created AppInfoContext");
-    ctx.refresh_apps().unwrap();
-    println!("Refreshed apps");
-    match ctx.get_frontmost_application() {
-        Ok(window) => {
-            println!("Found frontmost application: {}", window.name);
-            let name = window.name.clone();
-            let icon = window
-                .load_icon()
-                .ok()
-                .map(|i| {
-                    println!("Loading icon for {}", name);
-                    let png = i.to_png().unwrap();
-                    let encoded = STANDARD.encode(png.get_bytes());
-                    println!("Icon encoded successfully");
-                    encoded
-                });
-            println!("Returning app info: {} with icon: {}", name, icon.is_some());
-            (name, icon)
-        }
-        Err(e) => {
-            println!("Failed to get frontmost application: {:?}", e);
-            ("System".to_string(), None)
-        }
-    }
-}
-
 fn _process_icon_to_base64(path: &str) -> Result<String, Box<dyn std::error::Error>> {
     let img = image::open(path)?;
     let resized = img.resize(128, 128, image::imageops::FilterType::Lanczos3);
@@ -74,64 +47,90 @@ fn _process_icon_to_base64(path: &str) -> Result<String, Box<dyn std::error::Err
     Ok(STANDARD.encode(png_buffer))
 }
 
-pub fn detect_color(color: &str) -> bool {
-    let color = color.trim().to_lowercase();
+#[tauri::command]
+pub async fn fetch_page_meta(url: String) -> Result<(String, Option<String>), String> {
+    let metadata = meta_fetcher
+        ::fetch_metadata(&url)
+        .map_err(|e| format!("Failed to fetch metadata: {}", e))?;
 
-    // hex
-    if color.starts_with('#') && color.len() == color.trim_end_matches(char::is_whitespace).len() {
-        let hex = &color[1..];
-        return match hex.len() {
-            3 | 6 | 8 => hex.chars().all(|c| c.is_ascii_hexdigit()),
-            _ => false,
-        };
-    }
+    Ok((metadata.title.unwrap_or_else(|| "No title found".to_string()), metadata.image))
+}
 
-    // rgb/rgba
-    if
-        (color.starts_with("rgb(") || color.starts_with("rgba(")) &&
-        color.ends_with(")") &&
-        !color[..color.len() - 1].contains(")")
-    {
-        let values = color
-            .trim_start_matches("rgba(")
-            .trim_start_matches("rgb(")
-            .trim_end_matches(')')
-            .split(',')
-            .collect::<Vec<&str>>();
+/// Pretty-prints a JSON item for the kind specific "Format JSON" action.
+#[tauri::command]
+pub fn format_json(content: String) -> Result<String, String> {
+    crate::utils::detectors::pretty_print_json(&content).ok_or_else(|| "Invalid JSON".to_string())
+}
 
-        return match values.len() {
-            3 | 4 => values.iter().all(|v| v.trim().parse::<f32>().is_ok()),
-            _ => false,
-        };
-    }
+/// Converts the color item `id` to `format`.
+#[tauri::command]
+pub async fn convert_color(
+    pool: tauri::State<'_, SqlitePool>,
+    id: String,
+    format: ColorFormat
+) -> Result<String, String> {
+    converted_color(&pool, &id, format).await
+}
 
-    // hsl/hsla
-    if
-        (color.starts_with("hsl(") || color.starts_with("hsla(")) &&
-        color.ends_with(")") &&
-        !color[..color.len() - 1].contains(")")
-    {
-        let values = color
-            .trim_start_matches("hsla(")
-            .trim_start_matches("hsl(")
-            .trim_end_matches(')')
-            .split(',')
-            .collect::<Vec<&str>>();
+async fn converted_color(pool: &SqlitePool, id: &str, format: ColorFormat) -> Result<String, String> {
+    history::refuse_sensitive(pool, id).await?;
+    let content = history::get_content(pool, id).await?.ok_or("Item not found")?;
+    let color = Color::parse(&content).ok_or("Item is not a color")?;
+    color.format(format).ok_or_else(|| "Color has no CSS name".to_string())
+}
 
-        return match values.len() {
-            3 | 4 => values.iter().all(|v| v.trim().parse::<f32>().is_ok()),
-            _ => false,
-        };
-    }
+/// Luminance of `color` and its contrast against white, black and optionally
+/// `background`, all following WCAG 2.
+#[tauri::command]
+pub fn analyze_color(color: String, background: Option<String>) -> Result<ColorAnalysis, String> {
+    let parse = |value: &str| Color::parse(value).ok_or_else(|| format!("Invalid color: {}", value));
+    let color = parse(&color)?;
+    let white = Color::parse("white").unwrap();
+    let black = Color::parse("black").unwrap();
 
-    false
+    Ok(ColorAnalysis {
+        luminance: color.luminance(),
+        contrast_on_white: color.contrast_ratio(white),
+        contrast_on_black: color.contrast_ratio(black),
+        contrast: background
+            .map(|background| Ok::<_, String>(color.contrast_ratio(parse(&background)?)))
+            .transpose()?,
+    })
 }
 
-#[tauri::command]
-pub async fn fetch_page_meta(url: String) -> Result<(String, Option<String>), String> {
-    let metadata = meta_fetcher
-        ::fetch_metadata(&url)
-        .map_err(|e| format!("Failed to fetch metadata: {}", e))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
 
-    Ok((metadata.title.unwrap_or_else(|| "No title found".to_string()), metadata.image))
+    /// A single connection, every connection to `:memory:` is its own database.
+    async fn pool_with_color(sensitive: bool) -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:").await
+            .unwrap();
+        sqlx::query("CREATE TABLE history (id TEXT PRIMARY KEY, content TEXT, sensitive BOOLEAN)")
+            .execute(&pool).await
+            .unwrap();
+        sqlx::query("INSERT INTO history (id, content, sensitive) VALUES ('color', '#ff0000', ?)")
+            .bind(sensitive)
+            .execute(&pool).await
+            .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn converts_colors() {
+        let pool = pool_with_color(false).await;
+        assert_eq!(converted_color(&pool, "color", ColorFormat::Rgb).await.unwrap(), "rgb(255, 0, 0)");
+    }
+
+    #[tokio::test]
+    async fn refuses_sensitive_colors() {
+        let pool = pool_with_color(true).await;
+        assert_eq!(
+            converted_color(&pool, "color", ColorFormat::Rgb).await,
+            Err(history::SENSITIVE_ERROR.to_string())
+        );
+    }
 }
@@ -0,0 +1,195 @@
+use parking_lot::Mutex;
+use std::time::{ Duration, Instant };
+
+/// Holds back a polled value until it has stayed the same for `delay`, so a
+/// drag-select only yields the final selection rather than every step of it.
+pub struct Debouncer<T> {
+    delay: Duration,
+    pending: Option<(T, Instant)>,
+    last: Option<T>,
+}
+
+impl<T: Clone + PartialEq> Debouncer<T> {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            pending: None,
+            last: None,
+        }
+    }
+
+    pub fn set_delay(&mut self, delay: Duration) {
+        self.delay = delay;
+    }
+
+    /// Feeds the value seen at `now`. Returns it once it has been unchanged
+    /// for `delay`, and only if it differs from the last value returned.
+    pub fn observe(&mut self, value: T, now: Instant) -> Option<T> {
+        if !matches!(&self.pending, Some((pending, _)) if *pending == value) {
+            self.pending = Some((value, now));
+        }
+
+        let (pending, since) = self.pending.as_ref()?;
+        if now.duration_since(*since) < self.delay || self.last.as_ref() == Some(pending) {
+            return None;
+        }
+        self.last = Some(pending.clone());
+        self.last.clone()
+    }
+
+    /// Marks `value` as already returned, for values that shouldn't be
+    /// reported such as the one present at startup or written by the app.
+    pub fn settle(&mut self, value: T) {
+        self.last = Some(value);
+    }
+}
+
+/// A steady stream of events still lets one through after this many windows.
+const MAX_LATENCY_WINDOWS: u32 = 10;
+
+/// Collapses a burst of events into one, the last. Every event waits out the
+/// window and only the one not followed by another within it goes ahead,
+/// or the first to wake once the burst has run for `MAX_LATENCY_WINDOWS`.
+#[derive(Default)]
+pub struct Coalescer {
+    state: Mutex<Burst>,
+}
+
+#[derive(Default)]
+struct Burst {
+    generation: u64,
+    started: Option<tokio::time::Instant>,
+}
+
+impl Coalescer {
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(Burst {
+                generation: 0,
+                started: None,
+            }),
+        }
+    }
+
+    /// Records an event and returns whether it ended its burst, `false` when
+    /// another event arrived within `window`.
+    pub async fn settle(&self, window: Duration) -> bool {
+        let generation = {
+            let mut state = self.state.lock();
+            state.generation += 1;
+            state.started.get_or_insert_with(tokio::time::Instant::now);
+            state.generation
+        };
+        if !window.is_zero() {
+            tokio::time::sleep(window).await;
+        }
+
+        let mut state = self.state.lock();
+        let last = state.generation == generation;
+        let overdue = state.started.is_some_and(
+            |started| started.elapsed() >= window * MAX_LATENCY_WINDOWS
+        );
+        if last {
+            state.started = None;
+        } else if overdue {
+            state.started = Some(tokio::time::Instant::now());
+        }
+        last || overdue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::task::JoinSet;
+
+    const DELAY: Duration = Duration::from_millis(500);
+
+    fn at(start: Instant, ms: u64) -> Instant {
+        start + Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn drag_select_yields_final_selection_once() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(DELAY);
+
+        for (ms, text) in [(0, "q"), (100, "qu"), (200, "quic"), (300, "quick")] {
+            assert_eq!(debouncer.observe(text, at(start, ms)), None);
+        }
+        assert_eq!(debouncer.observe("quick", at(start, 700)), None);
+        assert_eq!(debouncer.observe("quick", at(start, 800)), Some("quick"));
+        assert_eq!(debouncer.observe("quick", at(start, 2000)), None);
+    }
+
+    #[test]
+    fn settled_values_are_not_reported() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(DELAY);
+        debouncer.settle("already there");
+
+        assert_eq!(debouncer.observe("already there", at(start, 0)), None);
+        assert_eq!(debouncer.observe("already there", at(start, 1000)), None);
+    }
+
+    #[test]
+    fn reselecting_after_another_selection_is_reported() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(DELAY);
+
+        debouncer.observe("a", at(start, 0));
+        assert_eq!(debouncer.observe("a", at(start, 500)), Some("a"));
+        debouncer.observe("b", at(start, 600));
+        assert_eq!(debouncer.observe("b", at(start, 1100)), Some("b"));
+        debouncer.observe("a", at(start, 1200));
+        assert_eq!(debouncer.observe("a", at(start, 1700)), Some("a"));
+    }
+
+    /// Fires events at the given offsets in ms and returns the offsets of
+    /// those that were let through.
+    async fn feed(window: Duration, offsets: &[u64]) -> Vec<u64> {
+        let coalescer = Arc::new(Coalescer::new());
+        let start = tokio::time::Instant::now();
+        let mut events = JoinSet::new();
+
+        for &offset in offsets {
+            tokio::time::sleep_until(start + Duration::from_millis(offset)).await;
+            let coalescer = coalescer.clone();
+            events.spawn(async move { (offset, coalescer.settle(window).await) });
+        }
+
+        let mut handled: Vec<u64> = events
+            .join_all().await
+            .into_iter()
+            .filter_map(|(offset, last)| last.then_some(offset))
+            .collect();
+        handled.sort();
+        handled
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn burst_yields_one_event() {
+        let handled = feed(Duration::from_millis(100), &[0, 5, 10, 40, 90]).await;
+        assert_eq!(handled, vec![90]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn separate_copies_are_each_handled() {
+        let handled = feed(Duration::from_millis(100), &[0, 20, 500, 510, 1200]).await;
+        assert_eq!(handled, vec![20, 510, 1200]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn steady_stream_is_flushed_every_ten_windows() {
+        let offsets: Vec<u64> = (0..60).map(|i| i * 50).collect();
+        let handled = feed(Duration::from_millis(100), &offsets).await;
+        assert_eq!(handled, vec![900, 1900, 2900, 2950]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn zero_window_handles_every_event() {
+        let handled = feed(Duration::ZERO, &[0, 1, 2, 3]).await;
+        assert_eq!(handled, vec![0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,419 @@
+use crate::utils::types::ContentKind;
+use chrono::{ DateTime, NaiveDate, NaiveDateTime };
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::net::{ IpAddr, SocketAddr };
+
+/// Recognizes one kind of structured text. Detectors look at the whole,
+/// trimmed text of an item and are tried in the order of `DETECTORS`, so
+/// the more specific ones come first.
+pub trait Detector: Send + Sync {
+    fn kind(&self) -> ContentKind;
+    fn matches(&self, text: &str) -> bool;
+}
+
+lazy_static! {
+    static ref DETECTORS: Vec<Box<dyn Detector>> = vec![
+        Box::new(JsonDetector),
+        Box::new(UuidDetector),
+        Box::new(IpAddressDetector),
+        Box::new(TimestampDetector),
+        Box::new(EmailDetector),
+        Box::new(PhoneDetector),
+        Box::new(PathDetector),
+        Box::new(YamlDetector)
+    ];
+    static ref UUID_REGEX: Regex = Regex::new(
+        r"^\{?[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\}?$"
+    ).unwrap();
+    static ref EMAIL_REGEX: Regex = Regex::new(
+        r"^(mailto:)?[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}$"
+    ).unwrap();
+    static ref PHONE_REGEX: Regex = Regex::new(r"^(\+|00)?\(?[0-9][0-9 ()./-]{4,}[0-9]$").unwrap();
+    /// `3.14159265`, `1714566600.123` and `12.345.678` are numbers, not phones
+    static ref DECIMAL_REGEX: Regex = Regex::new(r"^[0-9]+\.[0-9]+$|^[0-9]{1,3}(\.[0-9]{3})+$").unwrap();
+    /// Whitespace in the first segment has to be escaped, `/remind me` is a
+    /// chat command and not a path
+    static ref UNIX_PATH_REGEX: Regex = Regex::new(
+        r"^(~|\.{1,2})?/([^/\s\\\x00]|\\[^\n\x00])+(/[^\n\x00]*)?$|^/$"
+    ).unwrap();
+    static ref WINDOWS_PATH_REGEX: Regex = Regex::new(
+        r#"^([A-Za-z]:\\|\\\\[^\\\n]+\\)[^\n<>:"|?*]*$"#
+    ).unwrap();
+    static ref YAML_KEY_REGEX: Regex = Regex::new(r#"^\s*(- )?["']?[\w.-]+["']?:(\s|$)"#).unwrap();
+    static ref YAML_ITEM_REGEX: Regex = Regex::new(r"^\s*- \S").unwrap();
+}
+
+/// The first kind whose detector matches `text`.
+pub fn detect_kind(text: &str) -> Option<ContentKind> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    DETECTORS.iter()
+        .find(|detector| detector.matches(text))
+        .map(|detector| detector.kind())
+}
+
+/// Objects and arrays only, a bare `42` or `"text"` isn't worth tagging.
+pub struct JsonDetector;
+
+impl Detector for JsonDetector {
+    fn kind(&self) -> ContentKind {
+        ContentKind::Json
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        (text.starts_with('{') || text.starts_with('[')) &&
+            serde_json::from_str::<serde_json::Value>(text).is_ok()
+    }
+}
+
+pub struct UuidDetector;
+
+impl Detector for UuidDetector {
+    fn kind(&self) -> ContentKind {
+        ContentKind::Uuid
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        UUID_REGEX.is_match(text)
+    }
+}
+
+/// IPv4 and IPv6 addresses, optionally with a port or CIDR prefix length.
+pub struct IpAddressDetector;
+
+impl Detector for IpAddressDetector {
+    fn kind(&self) -> ContentKind {
+        ContentKind::IpAddress
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        if text.parse::<IpAddr>().is_ok() || text.parse::<SocketAddr>().is_ok() {
+            return true;
+        }
+        match text.split_once('/') {
+            Some((address, prefix)) =>
+                match (address.parse::<IpAddr>(), prefix.parse::<u8>()) {
+                    (Ok(IpAddr::V4(_)), Ok(prefix)) => prefix <= 32,
+                    (Ok(IpAddr::V6(_)), Ok(prefix)) => prefix <= 128,
+                    _ => false,
+                }
+            None => false,
+        }
+    }
+}
+
+/// RFC 3339 / RFC 2822 dates, plain `YYYY-MM-DD[ HH:MM[:SS]]` and unix
+/// timestamps in seconds or milliseconds between 2001 and 2100.
+pub struct TimestampDetector;
+
+const MIN_UNIX_SECONDS: i64 = 978_307_200;
+const MAX_UNIX_SECONDS: i64 = 4_102_444_800;
+
+impl Detector for TimestampDetector {
+    fn kind(&self) -> ContentKind {
+        ContentKind::Timestamp
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        if DateTime::parse_from_rfc3339(text).is_ok() || DateTime::parse_from_rfc2822(text).is_ok() {
+            return true;
+        }
+        if
+            ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M:%S%.f"]
+                .iter()
+                .any(|format| NaiveDateTime::parse_from_str(text, format).is_ok())
+        {
+            return true;
+        }
+        if NaiveDate::parse_from_str(text, "%Y-%m-%d").is_ok() {
+            return true;
+        }
+
+        if !text.chars().all(|c| c.is_ascii_digit()) {
+            return false;
+        }
+        let seconds = match text.len() {
+            10 => text.parse::<i64>().ok(),
+            13 => text.parse::<i64>().ok().map(|millis| millis / 1000),
+            _ => None,
+        };
+        seconds.is_some_and(|seconds| (MIN_UNIX_SECONDS..MAX_UNIX_SECONDS).contains(&seconds))
+    }
+}
+
+pub struct EmailDetector;
+
+impl Detector for EmailDetector {
+    fn kind(&self) -> ContentKind {
+        ContentKind::Email
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        EMAIL_REGEX.is_match(text)
+    }
+}
+
+/// Needs a leading `+` or digits in groups of at most eight, so plain and
+/// decimal numbers aren't phones.
+pub struct PhoneDetector;
+
+const MAX_PHONE_GROUP: usize = 8;
+
+impl Detector for PhoneDetector {
+    fn kind(&self) -> ContentKind {
+        ContentKind::Phone
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        let text = text.strip_prefix("tel:").unwrap_or(text);
+        if !PHONE_REGEX.is_match(text) || DECIMAL_REGEX.is_match(text) {
+            return false;
+        }
+        let groups: Vec<&str> = text
+            .split(|c: char| !c.is_ascii_digit())
+            .filter(|group| !group.is_empty())
+            .collect();
+        let digits: usize = groups
+            .iter()
+            .map(|group| group.len())
+            .sum();
+        let grouped =
+            groups.len() > 1 && groups.iter().all(|group| group.len() <= MAX_PHONE_GROUP);
+        (7..=15).contains(&digits) && (text.starts_with('+') || grouped)
+    }
+}
+
+/// A single absolute, home or relative Unix path, or a Windows drive or UNC path.
+pub struct PathDetector;
+
+impl Detector for PathDetector {
+    fn kind(&self) -> ContentKind {
+        ContentKind::Path
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        UNIX_PATH_REGEX.is_match(text) || WINDOWS_PATH_REGEX.is_match(text)
+    }
+}
+
+/// Block style mappings and lists. At least two `key:` lines are required so
+/// a single "Note: ..." line stays text.
+pub struct YamlDetector;
+
+impl Detector for YamlDetector {
+    fn kind(&self) -> ContentKind {
+        ContentKind::Yaml
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        let mut keys = 0;
+        for line in text.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "---" {
+                continue;
+            }
+            if YAML_KEY_REGEX.is_match(line) {
+                keys += 1;
+            } else if !YAML_ITEM_REGEX.is_match(line) && !line.starts_with([' ', '\t']) {
+                return false;
+            }
+        }
+        keys >= 2
+    }
+}
+
+/// Re-indents valid JSON with two spaces, keeping the key order of the input.
+pub fn pretty_print_json(text: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(text).ok()?;
+
+    let mut result = String::with_capacity(text.len() * 2);
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = text.trim().chars().peekable();
+
+    let newline = |result: &mut String, depth: usize| {
+        result.push('\n');
+        result.push_str(&"  ".repeat(depth));
+    };
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            result.push(c);
+            match c {
+                _ if escaped => {
+                    escaped = false;
+                }
+                '\\' => {
+                    escaped = true;
+                }
+                '"' => {
+                    in_string = false;
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                result.push(c);
+            }
+            '{' | '[' => {
+                result.push(c);
+                // Keep empty containers on one line
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                if matches!(chars.peek(), Some('}' | ']')) {
+                    result.push(chars.next().unwrap());
+                } else {
+                    depth += 1;
+                    newline(&mut result, depth);
+                }
+            }
+            '}' | ']' => {
+                depth -= 1;
+                newline(&mut result, depth);
+                result.push(c);
+            }
+            ',' => {
+                result.push(c);
+                newline(&mut result, depth);
+            }
+            ':' => result.push_str(": "),
+            c if c.is_whitespace() => {}
+            c => result.push(c),
+        }
+    }
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json() {
+        let detector = JsonDetector;
+        assert!(detector.matches(r#"{"name": "qopy", "tags": [1, 2]}"#));
+        assert!(detector.matches("[1, 2, 3]"));
+        assert!(!detector.matches("42"));
+        assert!(!detector.matches("{not json}"));
+    }
+
+    #[test]
+    fn yaml() {
+        let detector = YamlDetector;
+        assert!(detector.matches("name: qopy\nversion: 1.0\ntags:\n  - clipboard\n  - tauri"));
+        assert!(detector.matches("---\n- name: build\n  run: cargo build"));
+        assert!(!detector.matches("Note: the meeting moved"));
+        assert!(!detector.matches("Subject: hello\nThis is a normal email body."));
+    }
+
+    #[test]
+    fn email() {
+        let detector = EmailDetector;
+        assert!(detector.matches("jane.doe+qopy@example.co.uk"));
+        assert!(detector.matches("mailto:team@example.com"));
+        assert!(!detector.matches("jane@localhost"));
+        assert!(!detector.matches("write to jane@example.com"));
+    }
+
+    #[test]
+    fn phone() {
+        let detector = PhoneDetector;
+        assert!(detector.matches("+49 30 1234567"));
+        assert!(detector.matches("(555) 123-4567"));
+        assert!(detector.matches("tel:+14155552671"));
+        assert!(!detector.matches("5551234567"));
+        assert!(!detector.matches("12-34"));
+    }
+
+    #[test]
+    fn phone_rejects_decimal_numbers() {
+        let detector = PhoneDetector;
+        assert!(detector.matches("555.123.4567"));
+        assert!(!detector.matches("3.14159265"));
+        assert!(!detector.matches("12.345.678"));
+        assert!(!detector.matches("1714566600.123"));
+        assert!(!detector.matches("1714566600 123"));
+    }
+
+    #[test]
+    fn path() {
+        let detector = PathDetector;
+        assert!(detector.matches("/usr/local/bin"));
+        assert!(detector.matches("~/Documents/notes.md"));
+        assert!(detector.matches("../src/main.rs"));
+        assert!(detector.matches(r"C:\Users\jane\Desktop"));
+        assert!(detector.matches(r"\\server\share\file.txt"));
+        assert!(!detector.matches("// a comment"));
+        assert!(!detector.matches("and/or"));
+    }
+
+    #[test]
+    fn path_rejects_sentences() {
+        let detector = PathDetector;
+        assert!(detector.matches("/Users/jane/My Documents/report.pdf"));
+        assert!(detector.matches(r"/My\ Files/notes.txt"));
+        assert!(!detector.matches("/remind me tomorrow"));
+        assert!(!detector.matches("/giphy happy friday"));
+    }
+
+    #[test]
+    fn uuid() {
+        let detector = UuidDetector;
+        assert!(detector.matches("123e4567-e89b-12d3-a456-426614174000"));
+        assert!(detector.matches("{123E4567-E89B-12D3-A456-426614174000}"));
+        assert!(!detector.matches("123e4567-e89b-12d3-a456"));
+    }
+
+    #[test]
+    fn ip_address() {
+        let detector = IpAddressDetector;
+        assert!(detector.matches("192.168.1.10"));
+        assert!(detector.matches("::1"));
+        assert!(detector.matches("127.0.0.1:8080"));
+        assert!(detector.matches("10.0.0.0/8"));
+        assert!(!detector.matches("256.1.1.1"));
+        assert!(!detector.matches("10.0.0.0/33"));
+    }
+
+    #[test]
+    fn timestamp() {
+        let detector = TimestampDetector;
+        assert!(detector.matches("2024-05-01T12:30:00Z"));
+        assert!(detector.matches("Wed, 01 May 2024 12:30:00 +0000"));
+        assert!(detector.matches("2024-05-01 12:30"));
+        assert!(detector.matches("2024-05-01"));
+        assert!(detector.matches("1714566600"));
+        assert!(detector.matches("1714566600000"));
+        assert!(!detector.matches("1234"));
+        assert!(!detector.matches("9999999999"));
+    }
+
+    #[test]
+    fn pretty_prints_json_in_order() {
+        assert_eq!(
+            pretty_print_json(r#"{"b":1,"a":[true,{}],"s":"x, \"y\": {z}"}"#).unwrap(),
+            "{\n  \"b\": 1,\n  \"a\": [\n    true,\n    {}\n  ],\n  \"s\": \"x, \\\"y\\\": {z}\"\n}"
+        );
+        assert_eq!(pretty_print_json("[ ]").unwrap(), "[]");
+        assert_eq!(pretty_print_json("{oops"), None);
+    }
+
+    #[test]
+    fn pipeline_prefers_specific_kinds() {
+        assert_eq!(detect_kind("  192.168.0.1 "), Some(ContentKind::IpAddress));
+        assert_eq!(detect_kind("2024-05-01"), Some(ContentKind::Timestamp));
+        assert_eq!(detect_kind(r#"{"a": 1}"#), Some(ContentKind::Json));
+        assert_eq!(detect_kind("Just a sentence."), None);
+        assert_eq!(detect_kind(""), None);
+    }
+}
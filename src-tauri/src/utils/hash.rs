@@ -0,0 +1,24 @@
+use sha2::{ Digest, Sha256 };
+
+/// Hash used to deduplicate text-like items. The content type is part of the
+/// input so the same string stored as e.g. a link and as text stays distinct.
+pub fn hash_text(content_type: &str, content: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(content_type.as_bytes());
+    hasher.update(b":");
+    hasher.update(content.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Hashes the decoded pixels of an encoded image, so two captures of the same
+/// screenshot match even when their encoded bytes differ.
+pub fn hash_image(bytes: &[u8]) -> Result<String, image::ImageError> {
+    let image = image::load_from_memory(bytes)?.to_rgba8();
+
+    let mut hasher = Sha256::new();
+    hasher.update(b"image:");
+    hasher.update(image.width().to_le_bytes());
+    hasher.update(image.height().to_le_bytes());
+    hasher.update(image.as_raw());
+    Ok(format!("{:x}", hasher.finalize()))
+}
@@ -0,0 +1,365 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// Share of the combined score of the two best candidates the winner needs.
+const CONFIDENCE_THRESHOLD: f32 = 0.65;
+/// Minimum score before anything is labelled code at all, which keeps prose
+/// with the odd keyword in it out.
+const MIN_SCORE: f32 = 6.0;
+/// Matches of a single signal beyond this don't add to the score.
+const MAX_SIGNAL_HITS: usize = 3;
+/// Only the start of very large pastes is classified.
+const MAX_SAMPLE_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    pub language: &'static str,
+    pub confidence: f32,
+}
+
+struct Language {
+    name: &'static str,
+    signals: Vec<(Regex, f32)>,
+}
+
+fn language(name: &'static str, signals: &[(&str, f32)]) -> Language {
+    Language {
+        name,
+        signals: signals
+            .iter()
+            .map(|(pattern, weight)| (Regex::new(&format!("(?m){}", pattern)).unwrap(), *weight))
+            .collect(),
+    }
+}
+
+lazy_static! {
+    static ref LANGUAGES: Vec<Language> = vec![
+        language("Rust", &[
+            (r"\bfn\s+\w+\s*(<[^>]*>)?\s*\(", 3.0),
+            (r"\blet\s+mut\b", 3.0),
+            (r"\bimpl\b[^{\n]*\{", 3.0),
+            (r"\bpub(\(crate\))?\s+(fn|struct|enum|mod|use|trait|const)\b", 3.0),
+            (r"^\s*use\s+\w+(::[\w{}*, ]+)+;", 3.0),
+            (r"\b(println|eprintln|vec|format|assert_eq|assert|panic|write|writeln)!\s*[(\[]", 3.0),
+            (r"&mut\s|&self\b|&'\w+", 2.0),
+            (r"\bmatch\s+[\w.&]+\s*\{", 2.0),
+            (r"\b(Some|None|Ok|Err)\(", 1.0),
+            (r"\)\s*->\s*[\w&<(\[]", 1.0),
+            (r"#\[(derive|cfg|test|tauri)", 4.0),
+            (r"\w::\w", 0.5),
+        ]),
+        language("Python", &[
+            (r"^\s*def\s+\w+\s*\(.*\)\s*(->\s*[^:]+)?:\s*$", 4.0),
+            (r"^\s*class\s+\w+(\(.*\))?:\s*$", 4.0),
+            (r"^\s*from\s+[\w.]+\s+import\s+\w", 4.0),
+            (r"^\s*import\s+[\w.]+(\s+as\s+\w+)?\s*$", 2.0),
+            (r"^\s*(if|elif|for|while|with|try|except|else|finally)\b.*:\s*$", 2.0),
+            (r"\belif\b", 3.0),
+            (r"\bself\.\w+", 1.0),
+            (r"\b(None|True|False)\b", 1.0),
+            (r"__\w+__", 2.0),
+            (r"\bprint\(", 1.0),
+            (r"^\s*@\w+(\.\w+)*(\(.*\))?\s*$", 1.0),
+            (r"\b(lambda|yield|nonlocal)\b", 2.0),
+        ]),
+        language("JavaScript", &[
+            (r"\bfunction\s*\*?\s*\w*\s*\(", 3.0),
+            (r"\b(const|let|var)\s+[\w{}\[\], ]+\s*=", 1.5),
+            (r"\)\s*=>\s*[{(]?|\w\s*=>\s*[{(]?", 1.0),
+            (r"\bconsole\.(log|error|warn)\(", 3.0),
+            (r"\b(document|window)\.\w+", 2.0),
+            (r"\brequire\(\s*['\x22]", 3.0),
+            (r"^\s*import\s+.*\s+from\s+['\x22]", 3.0),
+            (r"^\s*export\s+(default|const|function|class|async)\b", 2.0),
+            (r"===|!==", 2.0),
+            (r"\b(undefined|null)\b", 0.5),
+            (r"\.then\(|\bawait\s+\w", 1.0),
+        ]),
+        language("TypeScript", &[
+            (r"\w\s*:\s*(string|number|boolean|any|void|unknown|never)\b", 3.0),
+            (r"^\s*(export\s+)?interface\s+\w+(<[^>]*>)?\s*\{", 3.0),
+            (r"^\s*(export\s+)?type\s+\w+(<[^>]*>)?\s*=", 3.0),
+            (r"\bas\s+(const|string|number|any|unknown)\b", 2.0),
+            (r"\b(private|public|protected|readonly)\s+\w+\s*[:?]", 2.0),
+            (r"\):\s*(Promise<|\w+(\[\])?\s*[{=])", 2.0),
+            (r"\bconsole\.(log|error|warn)\(", 2.0),
+            (r"^\s*import\s+.*\s+from\s+['\x22]", 2.0),
+            (r"^\s*export\s+(default|const|function|class|async)\b", 1.0),
+            (r"\b(const|let)\s+\w+\s*(:\s*[\w<>\[\]]+)?\s*=", 1.0),
+        ]),
+        language("Java", &[
+            (r"\bpublic\s+(static\s+)?(final\s+)?(abstract\s+)?(class|interface|enum|record)\s+\w+", 3.0),
+            (r"\bpublic\s+static\s+void\s+main\s*\(\s*String", 5.0),
+            (r"\bSystem\.(out|err)\.print", 5.0),
+            (r"\b(private|protected|public)\s+(static\s+)?(final\s+)?[A-Z]\w*(<[^>]*>)?(\[\])?\s+\w+\s*[;=(]", 2.0),
+            (r"@Override\b", 3.0),
+            (r"^\s*import\s+(static\s+)?(java|javax|org|com)\.[\w.*]+;", 4.0),
+            (r"^\s*package\s+[\w.]+;", 3.0),
+            (r"\bnew\s+[A-Z]\w*(<[^>]*>)?\(", 1.0),
+            (r"\bString\[\]", 2.0),
+            (r"\bthrows\s+\w+", 2.0),
+            (r"\binstanceof\b", 3.0),
+            (r"\bpublic\s+(boolean|int|long|double|void|String)\s+\w+\s*\(", 2.0),
+        ]),
+        language("C#", &[
+            (r"^\s*using\s+System(\.[\w.]+)?;", 5.0),
+            (r"\bConsole\.(Write|WriteLine|ReadLine)\(", 5.0),
+            (r"\{\s*get;\s*(private\s+)?set;\s*\}", 5.0),
+            (r"^\s*namespace\s+[\w.]+\s*[;{]?\s*$", 2.0),
+            (r"\bvar\s+\w+\s*=\s*new\b", 2.0),
+            (r"\basync\s+Task\b|\bTask<", 3.0),
+            (r"\bpublic\s+(static\s+)?(override\s+)?(void|string|int|bool)\s+[A-Z]\w*\s*\(", 2.0),
+            (r"\bstring\s+\w+\s*[=;]", 1.0),
+            (r"^\s*\[\w+(\(.*\))?\]\s*$", 1.0),
+        ]),
+        language("C", &[
+            (r"^\s*#include\s*<\w+\.h>", 3.0),
+            (r"^\s*#include\s*\x22[\w/]+\.h\x22", 2.0),
+            (r"\bprintf\s*\(", 2.0),
+            (r"\bint\s+main\s*\(", 2.0),
+            (r"\b(malloc|calloc|realloc|free|memcpy|strcpy|strlen)\s*\(", 2.0),
+            (r"\bstruct\s+\w+\s*\*", 2.0),
+            (r"\b(unsigned|char|void|int)\s*\*+\s*\w+", 2.0),
+            (r"^\s*#define\s+\w+", 2.0),
+            (r"\w->\w", 1.0),
+            (r"\bsizeof\s*\(", 1.0),
+            (r"\breturn\s+0;", 1.0),
+        ]),
+        language("C++", &[
+            (r"^\s*#include\s*<\w+>", 3.0),
+            (r"\bstd::\w+", 3.0),
+            (r"\bcout\s*<<|<<\s*(std::)?endl\b", 4.0),
+            (r"\btemplate\s*<", 3.0),
+            (r"^\s*using\s+namespace\s+\w+;", 4.0),
+            (r"^\s*namespace\s+\w+\s*\{", 2.0),
+            (r"\bclass\s+\w+\s*(:\s*(public|private|protected)\s+\w+)?\s*\{", 1.0),
+            (r"\b(public|private|protected):\s*$", 3.0),
+            (r"\bauto\s+&?\w+\s*[=:]", 2.0),
+            (r"\b(nullptr|constexpr|unique_ptr|shared_ptr)\b", 3.0),
+            (r"\bint\s+main\s*\(", 1.0),
+        ]),
+        language("Go", &[
+            (r"^\s*package\s+\w+\s*$", 3.0),
+            (r"\bfunc\s+(\(\w+\s+\*?\w+\)\s*)?\w+\s*\([^:)]*\)", 4.0),
+            (r"\w\s*:=\s*", 2.0),
+            (r"\bfmt\.\w+\(", 4.0),
+            (r"\bgo\s+func\b", 3.0),
+            (r"\bchan\s+\w+|<-\s*\w+", 2.0),
+            (r"\bif\s+err\s*!=\s*nil\b", 4.0),
+            (r"^\s*import\s*\($", 3.0),
+            (r"\bdefer\s+\w", 2.0),
+            (r"\[\]\w+\{", 2.0),
+        ]),
+        language("Ruby", &[
+            (r"^\s*def\s+(self\.)?\w+[?!]?(\s*\(.*\))?\s*$", 2.0),
+            (r"^\s*end\s*$", 3.0),
+            (r"\bputs\s", 3.0),
+            (r"^\s*require(_relative)?\s+['\x22]", 3.0),
+            (r"\bdo\s*\|\w+(,\s*\w+)*\|", 4.0),
+            (r"\b(attr_accessor|attr_reader|attr_writer)\b", 4.0),
+            (r"^\s*class\s+\w+(\s*<\s*[\w:]+)?\s*$", 2.0),
+            (r"^\s*module\s+\w+\s*$", 2.0),
+            (r"\.each(_with_index)?\b", 2.0),
+            (r"\bnil\b", 1.0),
+            (r"#\{[^}]+\}", 2.0),
+        ]),
+        language("PHP", &[
+            (r"<\?php", 8.0),
+            (r"\$\w+\s*=[^=]", 2.0),
+            (r"\$this->", 4.0),
+            (r"\bfunction\s+\w+\s*\(\s*(\w+\s+)?\$", 4.0),
+            (r"\becho\s+[\$'\x22]", 2.0),
+            (r"^\s*namespace\s+[\w\\]+;", 3.0),
+            (r"^\s*use\s+[\w\\]+;", 2.0),
+            (r"\w->\w+\(", 1.0),
+        ]),
+        language("Shell", &[
+            (r"^#!\s*/(usr/)?bin/(env\s+)?(ba|z|k|da)?sh\b", 8.0),
+            (
+                r"^\s*(sudo|apt|apt-get|brew|npm|npx|yarn|pnpm|cargo|git|docker|kubectl|cd|ls|mkdir|rm|cp|mv|chmod|chown|curl|wget|export|source|cat|grep|sed|awk|pip|ssh|scp|tar|systemctl|make)\s",
+                2.0,
+            ),
+            (r"\|\s*(grep|awk|sed|xargs|sort|uniq|head|tail|wc|tee|cut)\b", 3.0),
+            (r"^\s*(then|fi|done|esac)\s*$", 3.0),
+            (r"^\s*if\s+\[\[?\s", 4.0),
+            (r"\$\{\w+\}|\$\(|\$\d\b|\$@", 2.0),
+            (r"\s-{1,2}[a-zA-Z][\w-]*", 0.5),
+            (r"&&|\|\|", 0.5),
+            (r"^\s*\w+\(\)\s*\{", 2.0),
+        ]),
+        language("SQL", &[
+            (
+                r"(?i)^\s*(SELECT\s+.+\s+FROM|INSERT\s+INTO|UPDATE\s+\w+\s+SET|DELETE\s+FROM|CREATE\s+(TABLE|INDEX|VIEW|UNIQUE\s+INDEX)|ALTER\s+TABLE|DROP\s+(TABLE|INDEX))\b",
+                5.0,
+            ),
+            (r"(?i)^\s*(SELECT|FROM|WHERE|AND|OR)\b", 1.0),
+            (r"(?i)\b(INNER\s+JOIN|LEFT\s+JOIN|GROUP\s+BY|ORDER\s+BY|VALUES\s*\(|PRIMARY\s+KEY|NOT\s+NULL)\b", 2.0),
+            (r"\b(VARCHAR|INTEGER|TEXT|BOOLEAN|DATETIME)\b", 1.0),
+            (r";\s*$", 0.5),
+        ]),
+        language("HTML", &[
+            (r"(?i)<!DOCTYPE\s+html>", 8.0),
+            (
+                r"<(html|head|body|div|span|p|a|ul|ol|li|table|tr|td|script|style|img|input|form|button|section|nav|header|footer|meta|link)\b[^>]*>",
+                2.0,
+            ),
+            (r"</(html|head|body|div|span|p|a|ul|ol|li|table|tr|td|script|style|form|button|section|nav|header|footer)>", 2.0),
+            (r#"\b(class|href|src|id)="[^"]*""#, 1.0),
+        ]),
+        language("CSS", &[
+            (r"^\s*([.#]?[\w-]+|\*)([\s,>+~]+[.#]?[\w-]+)*(:{1,2}[\w-]+(\([^)]*\))?)?\s*\{\s*$", 2.0),
+            (r"^\s*[a-z-]+\s*:\s*[^;:{}]+;\s*$", 2.0),
+            (r"@(media|import|keyframes|font-face|supports)\b", 3.0),
+            (r"\b\d+(\.\d+)?(px|em|rem|vh|vw|pt)\b", 1.0),
+            (r"!important\b", 2.0),
+        ]),
+        language("Kotlin", &[
+            (r"\bfun\s+(<[^>]*>\s*)?[\w.]+\s*\(", 4.0),
+            (r"\bval\s+\w+\s*(:\s*[\w<>?]+)?\s*=", 3.0),
+            (r"\bvar\s+\w+\s*:\s*[\w<>?]+", 1.0),
+            (r"\b(data|sealed|open)\s+class\b", 4.0),
+            (r"\bprintln\(", 1.0),
+            (r"\bwhen\s*(\(.*\))?\s*\{", 3.0),
+            (r"\?\.|\?:", 1.0),
+        ]),
+        language("Swift", &[
+            (r"^\s*import\s+(UIKit|Foundation|SwiftUI|Combine)\s*$", 6.0),
+            (r"\bguard\s+let\b|\bif\s+let\b", 4.0),
+            (r"\bfunc\s+\w+\s*(<[^>]*>)?\(([^)]*:[^)]*)?\)\s*(->\s*[\w?\[\]]+\s*)?\{", 3.0),
+            (r"\b(let|var)\s+\w+\s*:\s*[A-Z][\w?\[\]]*", 2.0),
+            (r"\b(struct|class)\s+\w+\s*:\s*(View|Codable|Equatable|ObservableObject)\b", 4.0),
+            (r"@(State|Published|Binding|objc)\b", 4.0),
+            (r"\btry[?!]\s", 3.0),
+        ])
+    ];
+}
+
+/// Guesses the programming language of `text`, returning `None` unless one
+/// language clearly wins over the others.
+pub fn detect_language(text: &str) -> Option<Detection> {
+    let sample = sample(text.trim());
+    if sample.len() < 10 {
+        return None;
+    }
+
+    let mut scores: Vec<(&'static str, f32)> = LANGUAGES.iter()
+        .map(|language| (language.name, score(language, sample)))
+        .collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let (language, best) = scores[0];
+    let runner_up = scores.get(1).map_or(0.0, |(_, score)| *score);
+    // Keyword heavy code like SQL has few symbols, so it needs stronger signals instead
+    let min_score = if looks_like_code(sample) { MIN_SCORE } else { MIN_SCORE * 2.0 };
+    if best < min_score {
+        return None;
+    }
+
+    let confidence = best / (best + runner_up);
+    if confidence < CONFIDENCE_THRESHOLD {
+        return None;
+    }
+
+    Some(Detection { language, confidence })
+}
+
+fn score(language: &Language, text: &str) -> f32 {
+    language.signals
+        .iter()
+        .map(|(regex, weight)| (regex.find_iter(text).take(MAX_SIGNAL_HITS).count() as f32) * weight)
+        .sum()
+}
+
+fn sample(text: &str) -> &str {
+    if text.len() <= MAX_SAMPLE_BYTES {
+        return text;
+    }
+    let mut end = MAX_SAMPLE_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Prose is mostly words and sentence punctuation, code leans on symbols.
+fn looks_like_code(text: &str) -> bool {
+    let visible = text.chars().filter(|c| !c.is_whitespace()).count();
+    let symbols = text
+        .chars()
+        .filter(|c| "{}[]()<>;=$#:|&*/\\\"'".contains(*c))
+        .count();
+    (symbols as f32) / (visible.max(1) as f32) >= 0.04
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    /// Files in `tests/corpus/languages` are named `<language>_<n>.txt`,
+    /// where `prose` samples must not be classified at all.
+    fn expected_language(stem: &str) -> Option<&'static str> {
+        let prefix = stem.rsplit_once('_').map_or(stem, |(prefix, _)| prefix);
+        match prefix {
+            "prose" => None,
+            "rust" => Some("Rust"),
+            "python" => Some("Python"),
+            "javascript" => Some("JavaScript"),
+            "typescript" => Some("TypeScript"),
+            "java" => Some("Java"),
+            "csharp" => Some("C#"),
+            "c" => Some("C"),
+            "cpp" => Some("C++"),
+            "go" => Some("Go"),
+            "ruby" => Some("Ruby"),
+            "php" => Some("PHP"),
+            "shell" => Some("Shell"),
+            "sql" => Some("SQL"),
+            "html" => Some("HTML"),
+            "css" => Some("CSS"),
+            "kotlin" => Some("Kotlin"),
+            "swift" => Some("Swift"),
+            other => panic!("Unknown corpus language {}", other),
+        }
+    }
+
+    #[test]
+    fn classifies_corpus() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus/languages");
+        let mut failures = Vec::new();
+        let mut total = 0;
+
+        for entry in fs::read_dir(&dir).expect("Missing language corpus") {
+            let path = entry.unwrap().path();
+            let stem = path.file_stem().unwrap().to_string_lossy().into_owned();
+            let text = fs::read_to_string(&path).unwrap();
+            let expected = expected_language(&stem);
+            let detected = detect_language(&text).map(|detection| detection.language);
+            total += 1;
+            if detected != expected {
+                failures.push(format!("{}: expected {:?}, got {:?}", stem, expected, detected));
+            }
+        }
+
+        assert!(total > 0, "Language corpus is empty");
+        assert!(failures.is_empty(), "Misclassified samples:\n{}", failures.join("\n"));
+    }
+
+    #[test]
+    fn ignores_short_and_plain_text() {
+        assert_eq!(detect_language(""), None);
+        assert_eq!(detect_language("let x"), None);
+        assert_eq!(detect_language("Thanks, see you tomorrow!"), None);
+        assert_eq!(detect_language("https://example.com/path?query=1"), None);
+    }
+
+    #[test]
+    fn reports_confidence() {
+        let detection = detect_language(
+            "fn main() {\n    let mut total = 0;\n    println!(\"{}\", total);\n}"
+        ).unwrap();
+        assert_eq!(detection.language, "Rust");
+        assert!(detection.confidence >= CONFIDENCE_THRESHOLD && detection.confidence <= 1.0);
+    }
+}
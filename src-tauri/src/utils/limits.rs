@@ -0,0 +1,370 @@
+use crate::db::settings::CaptureLimits;
+use crate::utils::types::{ CaptureLimited, ContentType };
+use serde::{ Deserialize, Serialize };
+
+/// Characters of an oversized text kept by the `reference` action.
+const PREVIEW_CHARS: usize = 280;
+/// PNG size grows roughly with the pixel count, this leaves some headroom
+/// when downscaling an image to get under the byte limit.
+const BYTES_HEADROOM: f64 = 0.8;
+
+const SEPARATORS: [char; 2] = ['/', '\\'];
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitAction {
+    /// Don't record the copy at all
+    Skip,
+    /// Keep what fits: the text up to the limit with a marker, a downscaled
+    /// image, the first files
+    #[default]
+    Truncate,
+    /// Keep a pointer to the copy instead of its content: a preview of the
+    /// text, the image at thumbnail size, the folder the files are in
+    Reference,
+}
+
+/// A copy checked against the `CaptureLimits`.
+#[derive(Debug, PartialEq)]
+pub enum Limited<T> {
+    Within(T),
+    /// Over a limit. `value` is what's left to store, `None` when nothing is.
+    Over {
+        value: Option<T>,
+        notice: CaptureLimited,
+    },
+}
+
+/// How an oversized image is stored.
+#[derive(Debug, PartialEq)]
+pub enum ImageFit {
+    Skip,
+    /// Scaled down to at most this many pixels
+    Downscale(u64),
+    Thumbnail,
+}
+
+pub fn limit_text(text: String, limits: &CaptureLimits) -> Limited<String> {
+    let size = text.len() as u64;
+    let Some(limit) = limits.max_text_bytes.filter(|limit| size > *limit) else {
+        return Limited::Within(text);
+    };
+
+    let action = limits.text_action;
+    let value = match action {
+        LimitAction::Skip => None,
+        LimitAction::Truncate => {
+            let kept = &text[..floor_char_boundary(&text, limit as usize)];
+            Some(
+                format!(
+                    "{}\n\n[Truncated, {} of {} kept]",
+                    kept,
+                    format_bytes(kept.len() as u64),
+                    format_bytes(size)
+                )
+            )
+        }
+        LimitAction::Reference => {
+            let preview: String = text.chars().take(PREVIEW_CHARS).collect();
+            Some(format!("{}…\n\n[{} of text not stored]", preview.trim_end(), format_bytes(size)))
+        }
+    };
+
+    let message = format!(
+        "Copied text of {} is over the {} limit, {}",
+        format_bytes(size),
+        format_bytes(limit),
+        match action {
+            LimitAction::Skip => "it wasn't saved",
+            LimitAction::Truncate => "only its beginning was saved",
+            LimitAction::Reference => "only a preview was saved",
+        }
+    );
+    Limited::Over {
+        value,
+        notice: notice(ContentType::Text, action, size, limit, message),
+    }
+}
+
+/// Checks an image's pixel count and encoded size. Returns `None` when it's
+/// within both limits.
+pub fn check_image(
+    width: u32,
+    height: u32,
+    bytes: u64,
+    limits: &CaptureLimits
+) -> Option<(ImageFit, CaptureLimited)> {
+    let pixels = (width as u64) * (height as u64);
+    let pixel_limit = limits.max_image_pixels.filter(|limit| pixels > *limit);
+    let byte_limit = limits.max_image_bytes.filter(|limit| bytes > *limit);
+    if pixel_limit.is_none() && byte_limit.is_none() {
+        return None;
+    }
+
+    let action = limits.image_action;
+    let fit = match action {
+        LimitAction::Skip => ImageFit::Skip,
+        LimitAction::Reference => ImageFit::Thumbnail,
+        LimitAction::Truncate => {
+            let for_bytes = byte_limit.map(|limit| {
+                ((pixels as f64) * ((limit as f64) / (bytes as f64)) * BYTES_HEADROOM) as u64
+            });
+            ImageFit::Downscale(pixel_limit.into_iter().chain(for_bytes).min().unwrap_or(pixels).max(1))
+        }
+    };
+
+    let outcome = match action {
+        LimitAction::Skip => "it wasn't saved",
+        LimitAction::Truncate => "it was saved downscaled",
+        LimitAction::Reference => "only a thumbnail was saved",
+    };
+    // The pixel count is reported when both are over, it's what the user sees
+    let (size, limit, message) = match pixel_limit {
+        Some(limit) =>
+            (
+                pixels,
+                limit,
+                format!(
+                    "Copied image of {}×{} is over the {} limit, {}",
+                    width,
+                    height,
+                    format_pixels(limit),
+                    outcome
+                ),
+            ),
+        None => {
+            let limit = byte_limit.unwrap_or_default();
+            (
+                bytes,
+                limit,
+                format!(
+                    "Copied image of {} is over the {} limit, {}",
+                    format_bytes(bytes),
+                    format_bytes(limit),
+                    outcome
+                ),
+            )
+        }
+    };
+
+    Some((fit, notice(ContentType::Image, action, size, limit, message)))
+}
+
+pub fn limit_files(files: Vec<String>, limits: &CaptureLimits) -> Limited<Vec<String>> {
+    let count = files.len() as u64;
+    let Some(limit) = limits.max_files.filter(|limit| count > *limit) else {
+        return Limited::Within(files);
+    };
+
+    let action = limits.file_action;
+    let (value, outcome) = match action {
+        LimitAction::Skip => (None, "they weren't saved".to_string()),
+        LimitAction::Truncate =>
+            (
+                Some(files.into_iter().take(limit as usize).collect()),
+                format!("only the first {} were saved", limit),
+            ),
+        LimitAction::Reference =>
+            match common_folder(&files) {
+                Some(folder) => (Some(vec![folder]), "only their folder was saved".to_string()),
+                None => (None, "they share no folder and weren't saved".to_string()),
+            }
+    };
+
+    let message = format!("{} copied files are over the {} file limit, {}", count, limit, outcome);
+    Limited::Over {
+        value,
+        notice: notice(ContentType::File, action, count, limit, message),
+    }
+}
+
+fn notice(
+    content_type: ContentType,
+    action: LimitAction,
+    size: u64,
+    limit: u64,
+    message: String
+) -> CaptureLimited {
+    CaptureLimited {
+        content_type,
+        action,
+        size,
+        limit,
+        message,
+    }
+}
+
+/// Deepest folder containing all of `files`, `None` when that's the root.
+fn common_folder(files: &[String]) -> Option<String> {
+    let parent = |path: &str| {
+        path.trim_end_matches(SEPARATORS)
+            .rsplit_once(SEPARATORS)
+            .map(|(folder, _)| folder.to_string())
+    };
+
+    let mut folder = parent(files.first()?)?;
+    for file in &files[1..] {
+        while !(file.starts_with(&folder) && file[folder.len()..].starts_with(SEPARATORS)) {
+            folder = parent(&folder)?;
+        }
+    }
+    (!folder.is_empty()).then_some(folder)
+}
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    (0..=index.min(text.len()))
+        .rev()
+        .find(|i| text.is_char_boundary(*i))
+        .unwrap_or(0)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["bytes", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 { format!("{} bytes", bytes) } else { format!("{:.1} {}", size, UNITS[unit]) }
+}
+
+fn format_pixels(pixels: u64) -> String {
+    format!("{:.1} MP", (pixels as f64) / 1_000_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(action: LimitAction) -> CaptureLimits {
+        CaptureLimits {
+            max_text_bytes: Some(1024),
+            max_image_pixels: Some(1_000_000),
+            max_image_bytes: Some(2 * 1024 * 1024),
+            max_files: Some(3),
+            text_action: action,
+            image_action: action,
+            file_action: action,
+        }
+    }
+
+    fn files(paths: &[&str]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|path| path.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn small_copies_are_untouched() {
+        let limits = limits(LimitAction::Skip);
+        assert_eq!(limit_text("hello".to_string(), &limits), Limited::Within("hello".to_string()));
+        assert_eq!(check_image(800, 600, 1024, &limits), None);
+        assert_eq!(limit_files(files(&["/a", "/b"]), &limits), Limited::Within(files(&["/a", "/b"])));
+    }
+
+    #[test]
+    fn unset_limits_never_apply() {
+        let limits = CaptureLimits {
+            max_text_bytes: None,
+            max_image_pixels: None,
+            max_image_bytes: None,
+            max_files: None,
+            ..limits(LimitAction::Skip)
+        };
+        let text = "x".repeat(10_000);
+        assert_eq!(limit_text(text.clone(), &limits), Limited::Within(text));
+        assert_eq!(check_image(10_000, 10_000, u64::MAX, &limits), None);
+    }
+
+    #[test]
+    fn oversized_text_is_skipped_with_a_notice() {
+        let Limited::Over { value, notice } = limit_text("x".repeat(4096), &limits(LimitAction::Skip)) else {
+            panic!("expected the text to be over the limit");
+        };
+        assert_eq!(value, None);
+        assert_eq!((notice.content_type, notice.size, notice.limit), (ContentType::Text, 4096, 1024));
+        assert_eq!(notice.message, "Copied text of 4.0 KB is over the 1.0 KB limit, it wasn't saved");
+    }
+
+    #[test]
+    fn truncation_keeps_whole_characters_and_marks_the_cut() {
+        // Three byte characters, the limit falls in the middle of one
+        let text = "€".repeat(1000);
+        let Limited::Over { value: Some(value), .. } = limit_text(text, &limits(LimitAction::Truncate)) else {
+            panic!("expected a truncated text");
+        };
+        let (kept, marker) = value.split_once("\n\n").unwrap();
+        assert_eq!(kept, "€".repeat(341));
+        assert_eq!(marker, "[Truncated, 1023 bytes of 2.9 KB kept]");
+    }
+
+    #[test]
+    fn reference_keeps_a_preview() {
+        let Limited::Over { value: Some(value), .. } = limit_text(
+            "a".repeat(5000),
+            &limits(LimitAction::Reference)
+        ) else {
+            panic!("expected a preview");
+        };
+        assert!(value.starts_with(&format!("{}…", "a".repeat(PREVIEW_CHARS))));
+        assert!(value.ends_with("[4.9 KB of text not stored]"));
+    }
+
+    #[test]
+    fn large_images_are_downscaled_to_the_tighter_limit() {
+        let (fit, notice) = check_image(4000, 3000, 1024, &limits(LimitAction::Truncate)).unwrap();
+        assert_eq!(fit, ImageFit::Downscale(1_000_000));
+        assert_eq!(
+            notice.message,
+            "Copied image of 4000×3000 is over the 1.0 MP limit, it was saved downscaled"
+        );
+
+        // 8 MB for 0.5 MP, a quarter of the bytes with headroom
+        let (fit, notice) = check_image(1000, 500, 8 * 1024 * 1024, &limits(LimitAction::Truncate)).unwrap();
+        assert_eq!(fit, ImageFit::Downscale(100_000));
+        assert_eq!((notice.size, notice.limit), (8 * 1024 * 1024, 2 * 1024 * 1024));
+    }
+
+    #[test]
+    fn image_actions() {
+        let fit = |action| check_image(4000, 3000, 1024, &limits(action)).unwrap().0;
+        assert_eq!(fit(LimitAction::Skip), ImageFit::Skip);
+        assert_eq!(fit(LimitAction::Reference), ImageFit::Thumbnail);
+    }
+
+    #[test]
+    fn file_lists_are_cut_or_replaced_by_their_folder() {
+        let copied = files(&[
+            "/home/me/photos/2024/a.jpg",
+            "/home/me/photos/2024/b.jpg",
+            "/home/me/photos/2025/c.jpg",
+            "/home/me/photos/d.jpg",
+        ]);
+
+        let Limited::Over { value, notice } = limit_files(copied.clone(), &limits(LimitAction::Truncate)) else {
+            panic!("expected the files to be over the limit");
+        };
+        assert_eq!(value, Some(copied[..3].to_vec()));
+        assert_eq!(notice.message, "4 copied files are over the 3 file limit, only the first 3 were saved");
+
+        let Limited::Over { value, .. } = limit_files(copied, &limits(LimitAction::Reference)) else {
+            panic!("expected the files to be over the limit");
+        };
+        assert_eq!(value, Some(files(&["/home/me/photos"])));
+    }
+
+    #[test]
+    fn common_folder_respects_path_segments() {
+        assert_eq!(
+            common_folder(&files(&["/srv/app/log.txt", "/srv/application/log.txt"])),
+            Some("/srv".to_string())
+        );
+        assert_eq!(
+            common_folder(&files(&["C:\\Users\\me\\a.txt", "C:\\Users\\me\\b\\c.txt"])),
+            Some("C:\\Users\\me".to_string())
+        );
+        assert_eq!(common_folder(&files(&["/a.txt", "/b.txt"])), None);
+    }
+}
@@ -1,5 +1,15 @@
+pub mod apps;
+pub mod color;
 pub mod commands;
+pub mod debounce;
+pub mod detectors;
 pub mod favicon;
+pub mod hash;
+pub mod language;
+pub mod limits;
+pub mod secrets;
+pub mod thumbnail;
 pub mod types;
+pub mod window;
 pub mod logger;
 pub mod keys;
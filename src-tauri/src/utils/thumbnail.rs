@@ -0,0 +1,121 @@
+use image::{ imageops::FilterType, ImageFormat, ImageReader };
+use std::io::Cursor;
+use std::path::{ Path, PathBuf };
+
+/// Longest edge of a thumbnail in pixels, enough for the result list on a 2x display.
+pub const THUMBNAIL_SIZE: u32 = 256;
+
+const THUMBNAIL_SUFFIX: &str = ".thumb.png";
+
+/// Thumbnails are stored next to their image as `<name>.thumb.png`.
+pub fn thumbnail_path(image_path: &str) -> PathBuf {
+    let path = Path::new(image_path);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}{}", stem, THUMBNAIL_SUFFIX))
+}
+
+/// Decodes an image and returns a PNG no larger than `THUMBNAIL_SIZE` on
+/// either edge, along with the original width and height.
+pub fn make_thumbnail(bytes: &[u8]) -> Result<(Vec<u8>, u32, u32), String> {
+    let image = image::load_from_memory(bytes).map_err(|e| e.to_string())?;
+    let (width, height) = (image.width(), image.height());
+
+    let thumbnail = if width <= THUMBNAIL_SIZE && height <= THUMBNAIL_SIZE {
+        image
+    } else {
+        image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+    };
+
+    let mut png = Vec::new();
+    thumbnail.write_to(&mut Cursor::new(&mut png), ImageFormat::Png).map_err(|e| e.to_string())?;
+
+    Ok((png, width, height))
+}
+
+/// Width and height read from the image header, without decoding it.
+pub fn dimensions(bytes: &[u8]) -> Result<(u32, u32), String> {
+    ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?
+        .into_dimensions()
+        .map_err(|e| e.to_string())
+}
+
+/// Scales an image down to at most `max_pixels` pixels, keeping its aspect
+/// ratio, and returns it as PNG.
+pub fn downscale(bytes: &[u8], max_pixels: u64) -> Result<Vec<u8>, String> {
+    let image = image::load_from_memory(bytes).map_err(|e| e.to_string())?;
+    let pixels = (image.width() as f64) * (image.height() as f64);
+    let scale = ((max_pixels as f64) / pixels).sqrt().min(1.0);
+    let width = ((image.width() as f64) * scale).floor().max(1.0) as u32;
+    let height = ((image.height() as f64) * scale).floor().max(1.0) as u32;
+
+    let mut png = Vec::new();
+    image
+        .resize_exact(width, height, FilterType::Triangle)
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ DynamicImage, RgbaImage };
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgba8(RgbaImage::new(width, height))
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn thumbnail_sits_next_to_image() {
+        assert_eq!(
+            thumbnail_path("/data/images/3f2a.png"),
+            PathBuf::from("/data/images/3f2a.thumb.png")
+        );
+    }
+
+    #[test]
+    fn downscales_keeping_aspect_ratio() {
+        let (bytes, width, height) = make_thumbnail(&png(3840, 2160)).unwrap();
+        assert_eq!((width, height), (3840, 2160));
+
+        let thumbnail = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (256, 144));
+    }
+
+    #[test]
+    fn keeps_small_images_at_their_size() {
+        let (bytes, width, height) = make_thumbnail(&png(40, 20)).unwrap();
+        assert_eq!((width, height), (40, 20));
+
+        let thumbnail = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (40, 20));
+    }
+
+    #[test]
+    fn rejects_non_images() {
+        assert!(make_thumbnail(b"not an image").is_err());
+    }
+
+    #[test]
+    fn reads_dimensions_from_header() {
+        assert_eq!(dimensions(&png(640, 480)), Ok((640, 480)));
+        assert!(dimensions(b"not an image").is_err());
+    }
+
+    #[test]
+    fn downscales_to_pixel_budget() {
+        let bytes = downscale(&png(4000, 3000), 1_200_000).unwrap();
+        let image = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((image.width(), image.height()), (1264, 948));
+        assert!((image.width() as u64) * (image.height() as u64) <= 1_200_000);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::utils::limits::LimitAction;
 use chrono::{ DateTime, Utc };
 use serde::{ Deserialize, Serialize };
 use std::fmt;
@@ -7,12 +8,121 @@ use uuid::Uuid;
 pub struct HistoryItem {
     pub id: String,
     pub source: String,
+    /// Icon of the source app as captured. Stored once per app in `sources`,
+    /// listings leave it empty and reference the app through `source_id`.
     pub source_icon: Option<String>,
+    #[serde(default)]
+    pub source_id: Option<i64>,
+    /// Executable or bundle path of the source app, only set on the capture path
+    #[serde(default, skip_serializing)]
+    pub source_path: Option<String>,
     pub content_type: ContentType,
     pub content: String,
     pub favicon: Option<String>,
     pub timestamp: DateTime<Utc>,
     pub language: Option<String>,
+    /// Title of the window the item was copied from
+    #[serde(default)]
+    pub window_title: Option<String>,
+    /// Page the item was copied from, when the browser exposed it
+    #[serde(default)]
+    pub source_url: Option<String>,
+    /// Structured kind of a text item, see `utils::detectors`
+    #[serde(default)]
+    pub kind: Option<ContentKind>,
+    /// Selection the item was captured from, PRIMARY only on Linux
+    #[serde(default)]
+    pub selection: Selection,
+    #[serde(default)]
+    pub pinned: bool,
+    /// Precomputed dedup hash, only set on the capture path. When `None`,
+    /// `add_history_item` derives it from the content.
+    #[serde(default, skip_serializing)]
+    pub content_hash: Option<String>,
+    /// Marked as secret on capture, `content` is masked in listings
+    #[serde(default)]
+    pub sensitive: bool,
+    /// When set, the item is purged automatically once this has passed
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Pixel size of an image item, recorded when its thumbnail is made
+    #[serde(default)]
+    pub width: Option<u32>,
+    #[serde(default)]
+    pub height: Option<u32>,
+    /// Rich formats stored for the item next to its plain `content`
+    #[serde(default)]
+    pub rich_formats: Vec<RichFormat>,
+    /// Rich payloads captured with the item, stored by `add_history_item`
+    #[serde(default, skip_serializing)]
+    pub representations: Vec<(RichFormat, String)>,
+}
+
+/// An application items were copied from, with how many items it has in
+/// the history.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Source {
+    pub id: i64,
+    pub name: String,
+    pub path: Option<String>,
+    pub icon: Option<String>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub count: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct SearchResult {
+    #[serde(flatten)]
+    pub item: HistoryItem,
+    pub snippet: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct ImageSweepReport {
+    pub files: Vec<String>,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LanguageCount {
+    pub language: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ColorAnalysis {
+    pub luminance: f64,
+    pub contrast_on_white: f64,
+    pub contrast_on_black: f64,
+    /// Contrast against the requested background, if one was given
+    pub contrast: Option<f64>,
+}
+
+/// Payload of the `capture-limited` event, sent when a copy was over one of
+/// the `CaptureLimits`.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct CaptureLimited {
+    pub content_type: ContentType,
+    pub action: LimitAction,
+    /// Size of the copy in the unit of the limit it exceeded
+    pub size: u64,
+    pub limit: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct CaptureStatus {
+    pub paused: bool,
+    /// When a timed pause ends, `None` while paused indefinitely
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct ImportReport {
+    pub added: usize,
+    pub updated: usize,
+    pub skipped: usize,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
@@ -26,10 +136,39 @@ pub enum ContentType {
     Code,
 }
 
+/// The regular clipboard, or the X11/Wayland PRIMARY selection that holds
+/// the last selected text and is pasted with a middle click.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Selection {
+    #[default]
+    Clipboard,
+    Primary,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum RichFormat {
+    Html,
+    Rtf,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentKind {
+    Json,
+    Yaml,
+    Email,
+    Phone,
+    Path,
+    Uuid,
+    IpAddress,
+    Timestamp,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct InfoText {
     pub source: String,
-    pub content_type: ContentType,
     pub characters: i32,
     pub words: i32,
     pub copied: DateTime<Utc>,
@@ -38,7 +177,6 @@ pub struct InfoText {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct InfoImage {
     pub source: String,
-    pub content_type: ContentType,
     pub dimensions: String,
     pub size: i64,
     pub copied: DateTime<Utc>,
@@ -47,16 +185,15 @@ pub struct InfoImage {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct InfoFile {
     pub source: String,
-    pub content_type: ContentType,
     pub path: String,
     pub filesize: i64,
+    pub exists: bool,
     pub copied: DateTime<Utc>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct InfoLink {
     pub source: String,
-    pub content_type: ContentType,
     pub title: Option<String>,
     pub url: String,
     pub characters: i32,
@@ -66,21 +203,56 @@ pub struct InfoLink {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct InfoColor {
     pub source: String,
-    pub content_type: ContentType,
     pub hex: String,
     pub rgb: String,
+    pub hsl: String,
     pub copied: DateTime<Utc>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct InfoCode {
     pub source: String,
-    pub content_type: ContentType,
     pub language: String,
     pub lines: i32,
     pub copied: DateTime<Utc>,
 }
 
+/// Metadata of a history item, tagged by `content_type` like the item itself.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "content_type", rename_all = "lowercase")]
+pub enum ItemInfo {
+    Text(InfoText),
+    Image(InfoImage),
+    File(InfoFile),
+    Link(InfoLink),
+    Color(InfoColor),
+    Code(InfoCode),
+}
+
+/// Why a stored image couldn't be read, tagged by `kind` for the frontend.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum ImageError {
+    /// No image item with the requested id
+    NotFound,
+    /// The item exists but its file is gone
+    MissingFile,
+    /// The stored path points outside the images directory
+    AccessDenied,
+    Failed(String),
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImageError::NotFound => write!(f, "Image not found"),
+            ImageError::MissingFile => write!(f, "Image file is missing"),
+            ImageError::AccessDenied => write!(f, "Image is outside the images directory"),
+            ImageError::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
 impl fmt::Display for ContentType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -94,6 +266,75 @@ impl fmt::Display for ContentType {
     }
 }
 
+impl fmt::Display for RichFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RichFormat::Html => write!(f, "html"),
+            RichFormat::Rtf => write!(f, "rtf"),
+        }
+    }
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Selection::Clipboard => write!(f, "clipboard"),
+            Selection::Primary => write!(f, "primary"),
+        }
+    }
+}
+
+impl fmt::Display for ContentKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContentKind::Json => write!(f, "json"),
+            ContentKind::Yaml => write!(f, "yaml"),
+            ContentKind::Email => write!(f, "email"),
+            ContentKind::Phone => write!(f, "phone"),
+            ContentKind::Path => write!(f, "path"),
+            ContentKind::Uuid => write!(f, "uuid"),
+            ContentKind::IpAddress => write!(f, "ip_address"),
+            ContentKind::Timestamp => write!(f, "timestamp"),
+        }
+    }
+}
+
+impl ContentKind {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "json" => Some(ContentKind::Json),
+            "yaml" => Some(ContentKind::Yaml),
+            "email" => Some(ContentKind::Email),
+            "phone" => Some(ContentKind::Phone),
+            "path" => Some(ContentKind::Path),
+            "uuid" => Some(ContentKind::Uuid),
+            "ip_address" => Some(ContentKind::IpAddress),
+            "timestamp" => Some(ContentKind::Timestamp),
+            _ => None,
+        }
+    }
+}
+
+impl Selection {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "clipboard" => Some(Selection::Clipboard),
+            "primary" => Some(Selection::Primary),
+            _ => None,
+        }
+    }
+}
+
+impl RichFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "html" => Some(RichFormat::Html),
+            "rtf" => Some(RichFormat::Rtf),
+            _ => None,
+        }
+    }
+}
+
 impl From<String> for ContentType {
     fn from(s: String) -> Self {
         match s.to_lowercase().as_str() {
@@ -121,11 +362,25 @@ impl HistoryItem {
             id: Uuid::new_v4().to_string(),
             source,
             source_icon,
+            source_id: None,
+            source_path: None,
             content_type,
             content,
             favicon,
             timestamp: Utc::now(),
             language,
+            window_title: None,
+            source_url: None,
+            kind: None,
+            selection: Selection::Clipboard,
+            pinned: false,
+            content_hash: None,
+            sensitive: false,
+            expires_at: None,
+            width: None,
+            height: None,
+            rich_formats: Vec::new(),
+            representations: Vec::new(),
         }
     }
 
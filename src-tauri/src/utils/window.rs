@@ -0,0 +1,101 @@
+use url::Url;
+
+/// Clipboard formats browsers use to record the page a copy came from.
+pub const SOURCE_URL_FORMATS: &[&str] = &[
+    // Chromium on macOS, Linux and Windows
+    "org.chromium.source-url",
+    "chromium/x-source-url",
+    "Chromium internal source URL",
+    // Firefox, the URL followed by the page title
+    "text/x-moz-url-priv",
+];
+
+/// Windows' CF_HTML, whose header carries a `SourceURL:` line.
+pub const HTML_FORMAT: &str = "HTML Format";
+
+/// Title of the focused window. Empty on macOS without the screen recording
+/// permission, which is reported as `None` as well.
+pub fn active_window_title() -> Option<String> {
+    active_win_pos_rs
+        ::get_active_window()
+        .ok()
+        .map(|window| window.title.trim().to_string())
+        .filter(|title| !title.is_empty())
+}
+
+/// Reads the URL out of one of `SOURCE_URL_FORMATS`. Firefox stores it as
+/// UTF-16, Chromium as UTF-8.
+pub fn parse_source_url(bytes: &[u8]) -> Option<String> {
+    let text = if looks_like_utf16(bytes) {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(bytes).into_owned()
+    };
+
+    web_url(text.trim_matches('\0').lines().next()?)
+}
+
+/// Reads the `SourceURL:` header of a CF_HTML payload.
+pub fn html_source_url(html: &str) -> Option<String> {
+    html.lines()
+        .take_while(|line| !line.trim_start().starts_with('<'))
+        .find_map(|line| line.strip_prefix("SourceURL:"))
+        .and_then(web_url)
+}
+
+/// Only http(s) pages are worth keeping, `about:blank` and friends aren't.
+fn web_url(value: &str) -> Option<String> {
+    let url = Url::parse(value.trim()).ok()?;
+    matches!(url.scheme(), "http" | "https").then(|| url.to_string())
+}
+
+fn looks_like_utf16(bytes: &[u8]) -> bool {
+    bytes.len() >= 4 && bytes.len() % 2 == 0 && bytes[1] == 0 && bytes[3] == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn reads_chromium_source_url() {
+        assert_eq!(
+            parse_source_url(b"https://acme.atlassian.net/browse/PROJ-12"),
+            Some("https://acme.atlassian.net/browse/PROJ-12".to_string())
+        );
+    }
+
+    #[test]
+    fn reads_firefox_source_url() {
+        let bytes = utf16("https://example.com/docs\nExample Docs\0");
+        assert_eq!(parse_source_url(&bytes), Some("https://example.com/docs".to_string()));
+    }
+
+    #[test]
+    fn reads_cf_html_header() {
+        let html = "Version:0.9\r\nStartHTML:0000000105\r\nEndHTML:0000000199\r\n\
+            SourceURL:https://github.com/rust-lang/rust/issues/1\r\n\
+            <html><body>SourceURL:https://evil.example</body></html>";
+        assert_eq!(
+            html_source_url(html),
+            Some("https://github.com/rust-lang/rust/issues/1".to_string())
+        );
+        assert_eq!(html_source_url("<html>SourceURL:https://evil.example</html>"), None);
+    }
+
+    #[test]
+    fn ignores_non_web_urls() {
+        assert_eq!(parse_source_url(b"about:blank"), None);
+        assert_eq!(parse_source_url(b"file:///etc/passwd"), None);
+        assert_eq!(parse_source_url(b"not a url"), None);
+        assert_eq!(parse_source_url(b""), None);
+    }
+}
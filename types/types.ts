@@ -9,15 +9,56 @@ export enum ContentType {
   Code = "code",
 }
 
+export enum RichFormat {
+  Html = "html",
+  Rtf = "rtf",
+}
+
+export enum Selection {
+  Clipboard = "clipboard",
+  Primary = "primary",
+}
+
+export enum ContentKind {
+  Json = "json",
+  Yaml = "yaml",
+  Email = "email",
+  Phone = "phone",
+  Path = "path",
+  Uuid = "uuid",
+  IpAddress = "ip_address",
+  Timestamp = "timestamp",
+}
+
+export enum ColorFormat {
+  Hex = "hex",
+  Rgb = "rgb",
+  Hsl = "hsl",
+  Hwb = "hwb",
+  Oklch = "oklch",
+  Name = "name",
+}
+
 export class HistoryItem {
   id: string;
   source: string;
   source_icon?: string;
+  source_id?: number;
   content_type: ContentType;
   content: string;
   favicon?: string;
   timestamp: Date;
   language?: string;
+  window_title?: string;
+  source_url?: string;
+  kind?: ContentKind;
+  selection?: Selection;
+  pinned: boolean;
+  sensitive: boolean;
+  expires_at?: Date;
+  width?: number;
+  height?: number;
+  rich_formats: RichFormat[];
 
   constructor(
     source: string,
@@ -35,6 +76,9 @@ export class HistoryItem {
     this.favicon = favicon;
     this.timestamp = new Date();
     this.language = language;
+    this.pinned = false;
+    this.sensitive = false;
+    this.rich_formats = [];
   }
 
   toRow(): [
@@ -60,6 +104,103 @@ export class HistoryItem {
   }
 }
 
+export interface SearchResult extends HistoryItem {
+  /** Matched excerpt, hits are wrapped in \u0002 ... \u0003 */
+  snippet?: string;
+}
+
+export interface ColorAnalysis {
+  luminance: number;
+  contrast_on_white: number;
+  contrast_on_black: number;
+  contrast: number | null;
+}
+
+export interface LanguageCount {
+  language: string;
+  count: number;
+}
+
+export interface ImageSweepReport {
+  files: string[];
+  bytes: number;
+}
+
+export interface CaptureStatus {
+  paused: boolean;
+  until: string | null;
+}
+
+export interface CapturePauseSettings {
+  keybind: string[];
+  default_minutes: number | null;
+  persist: boolean;
+}
+
+export interface ImportReport {
+  added: number;
+  updated: number;
+  skipped: number;
+}
+
+export interface EncryptionStatus {
+  enabled: boolean;
+  unlocked: boolean;
+  key_source: "passphrase" | "key_file";
+}
+
+export interface AppFilter {
+  allowlist_mode: boolean;
+  blocklist: string[];
+  allowlist: string[];
+}
+
+export type SecretAction = "redact" | "expire" | "skip";
+
+export interface CustomSecretRule {
+  name: string;
+  pattern: string;
+  action?: SecretAction;
+}
+
+export interface SecretDetection {
+  enabled: boolean;
+  default_action: SecretAction;
+  actions: Record<string, SecretAction>;
+  disabled_rules: string[];
+  custom_rules: CustomSecretRule[];
+  ttl_seconds: number;
+}
+
+export type LimitAction = "skip" | "truncate" | "reference";
+
+export interface CaptureLimits {
+  max_text_bytes?: number;
+  max_image_pixels?: number;
+  max_image_bytes?: number;
+  max_files?: number;
+  text_action: LimitAction;
+  image_action: LimitAction;
+  file_action: LimitAction;
+}
+
+export interface CaptureLimited {
+  content_type: ContentType;
+  action: LimitAction;
+  size: number;
+  limit: number;
+  message: string;
+}
+
+export interface CaptureDebounce {
+  window_ms: number;
+}
+
+export interface PrimarySelectionSettings {
+  enabled: boolean;
+  debounce_ms: number;
+}
+
 export interface Settings {
   key: string;
   value: string;
@@ -86,6 +227,7 @@ export interface InfoFile {
   content_type: ContentType.File;
   path: string;
   filesize: number;
+  exists: boolean;
   copied: Date;
 }
 
@@ -114,3 +256,25 @@ export interface InfoCode {
   lines: number;
   copied: Date;
 }
+
+export interface Source {
+  id: number;
+  name: string;
+  path?: string;
+  icon?: string;
+  first_seen: string;
+  last_seen: string;
+  count: number;
+}
+
+export type ImageError =
+  | { kind: "not_found" | "missing_file" | "access_denied" }
+  | { kind: "failed"; message: string };
+
+export type ItemInfo =
+  | InfoText
+  | InfoImage
+  | InfoFile
+  | InfoLink
+  | InfoColor
+  | InfoCode;